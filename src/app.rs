@@ -1,16 +1,56 @@
 use crate::{
-    config::CubeConfig,
+    cli::Args,
+    config::{ConfigError, CubeConfig, Mode, ScrambleConfig, SessionConfig},
     countdown::Countdown,
-    history::{History, Penalty},
-    scramble::Scramble,
-    timer::Timer,
+    cube::Cube,
+    history::{Entry, History, LoadReport, ManualEntry, MultiBld, Penalty},
+    input::{
+        self,
+        smartcube::SmartCube,
+        stackmat::{Packet, Stackmat, Status},
+        EventSource, InputEvent,
+    },
+    library::{Drill, Library, LibraryCase, Recall},
+    reconstruction::Reconstruction,
+    scramble::{self, Layers, Move, Scramble},
+    scramble_set::ScrambleSet,
+    settings::{Kind, Settings, SETTINGS},
+    solver::{self, Solution},
+    timer::{State as TimerState, Timer},
+    trainer::{self, Case},
     ui,
 };
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use std::fs;
 use std::io;
-use std::time::{Duration, Instant};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 use tui::{backend::Backend, Terminal};
 
+const MAX_PHASES: usize = 5;
+const FMC_TIME_LIMIT: Duration = Duration::from_secs(60 * 60);
+
+/// What the text typed in by the user is for
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum Prompt {
+    Time,
+    Moves,
+    /// Number of cubes for a multi-blind attempt
+    Cubes,
+    /// Number of cubes solved in the multi-blind attempt that just ended
+    SolvedCubes,
+    /// A case and its algorithms for the library
+    Alg,
+    /// The first step planned during inspection
+    Plan,
+    /// Name of a new profile
+    Profile,
+    /// Value of the setting selected in the settings screen
+    Setting,
+}
+
 #[derive(PartialEq, Eq)]
 pub enum AppState<'a> {
     Idle,
@@ -18,6 +58,26 @@ pub enum AppState<'a> {
     KeyHold,
     Timer,
     ShowHelp,
+    Typing(Prompt),
+    Detail,
+    /// Writing down a fewest moves solution
+    Fmc,
+    /// Official result of the competition simulation round that just ended
+    RoundSummary,
+    /// Browsing the algorithm library
+    Library,
+    /// Setting up and timing a case of the library
+    Drill,
+    /// Rating how well the drilled algorithm was remembered
+    Recall,
+    /// The planned step next to its optimal solutions
+    PlanResult,
+    /// Solution of the scramble of the solve shown in the detail view
+    Solution,
+    /// Picking the profile whose config and history are used
+    Profiles,
+    /// Editing the config file
+    Settings,
     ShouldQuit,
     Confirm(&'a str),
 }
@@ -31,58 +91,674 @@ pub struct App<'a> {
     pub countdown: Countdown,
     pub key_hold: Countdown,
     pub config: CubeConfig,
+    pub input: String,
+    pub input_error: Option<String>,
+    pub events: Receiver<InputEvent>,
+    /// Tracked state of a connected smart cube
+    pub cube: Cube,
+    /// Smart cube moves of the solve in progress
+    pub solve_moves: Reconstruction,
+    /// History entry shown in the detail view
+    pub selected: usize,
+    /// Every scramble of the current multi puzzle attempt
+    pub scrambles: Vec<Scramble>,
+    /// Status shown until the next key press
+    pub message: Option<String>,
+    /// Id of the competition simulation round in progress
+    pub round: usize,
+    /// Scrambles served in order instead of generating them
+    pub scramble_set: Option<ScrambleSet>,
+    /// Number of scrambles of the set served so far
+    pub set_index: usize,
+    /// Algorithm trainer case the scramble sets up
+    pub case: Option<&'static Case>,
+    pub library: Library,
+    /// Library case shown in the library view
+    pub library_selected: usize,
+    /// Library case being drilled
+    pub drill: Option<Drill>,
+    /// Optimal solutions of the step that was just planned
+    pub solutions: Vec<Scramble>,
+    /// Search for `solutions` still running
+    pub searching: Option<Receiver<Vec<Scramble>>>,
+    /// Solver's take on the scramble of the selected history entry
+    pub solution: Option<Solution>,
+    /// Solver still running for `solution`
+    pub solving: Option<Receiver<Solution>>,
+    /// When the loaded config file was written
+    config_modified: Option<SystemTime>,
+    /// Values of the config file as last loaded, a reload only overrides what
+    /// was changed at runtime if it changed in the file too
+    file_scramble: ScrambleConfig,
+    file_session: SessionConfig,
+    /// Profile shown in the profile picker
+    pub profile_selected: usize,
+    /// Config file edited in the settings screen
+    pub settings: Option<Settings>,
+    /// Setting shown in the settings screen
+    pub setting_selected: usize,
+    /// Lines of the history file that were skipped, shown until a key is
+    /// pressed
+    pub history_report: Option<LoadReport>,
+    /// Number of cubes to ask for once back to idle, the config switched to
+    /// multi-blind in another screen
+    ask_cubes: bool,
 }
 
 impl<'a> App<'a> {
-    pub fn new(title: &'a str) -> Self {
-        let config = CubeConfig::new().unwrap();
+    pub fn new(title: &'a str, args: Args, mut config: CubeConfig) -> Result<Self, ConfigError> {
+        let history_path = CubeConfig::get_history_path()?;
+        let library_path = CubeConfig::get_library_path()?;
+        if args.stackmat.is_some() {
+            config.stackmat = args.stackmat;
+        }
+        if args.smartcube.is_some() {
+            config.smartcube = args.smartcube;
+        }
+        if let Some(seed) = args.seed.or(config.scramble.seed) {
+            scramble::seed(seed);
+        }
+        scramble::min_moves(config.scramble.min_moves);
 
-        App {
+        let (tx, events) = mpsc::channel();
+        let mut sources: Vec<Box<dyn EventSource>> = vec![];
+        if let Some(stackmat) = config.stackmat.clone() {
+            sources.push(Box::new(Stackmat(stackmat)));
+        }
+        if let Some(smartcube) = config.smartcube.clone() {
+            sources.push(Box::new(SmartCube(smartcube)));
+        }
+        for source in sources {
+            input::spawn(source, tx.clone());
+        }
+
+        let mut message = None;
+        let scramble_set = match (args.scrambles, &config.scramble.file) {
+            (Some(set), _) => Some(set),
+            (None, Some(path)) => match ScrambleSet::load(path) {
+                Ok(set) => Some(set),
+                Err(err) => {
+                    message = Some(err.to_string());
+                    None
+                }
+            },
+            (None, None) => None,
+        };
+
+        // the number of cubes of a multi-blind attempt is asked up front,
+        // after picking a profile when there is a choice
+        let state = match config.session.mode {
+            _ if args.profile.is_none() && CubeConfig::profiles().len() > 1 => AppState::Profiles,
+            Mode::MultiBld => AppState::Typing(Prompt::Cubes),
+            _ => AppState::Idle,
+        };
+
+        let (history, report) = History::from_csv(&history_path);
+        let mut app = App {
             title,
             timer: Timer::new(),
-            state: AppState::Idle,
+            state,
             tick_rate: Duration::from_millis(100),
-            scramble: Scramble::new_rand(config.inspection.length),
-            history: History::from_csv(&CubeConfig::get_history_path().unwrap()),
+            scramble: Scramble::new_rand(config.scramble.length),
+            history,
             countdown: Countdown::new(Duration::from_secs(config.inspection.length as u64)),
             key_hold: Countdown::new(Duration::from_secs(config.inspection.key_hold as u64)),
+            input: String::new(),
+            input_error: None,
+            events,
+            cube: Cube::default(),
+            solve_moves: Reconstruction::default(),
+            selected: 0,
+            scrambles: vec![],
+            message,
+            round: 0,
+            scramble_set,
+            set_index: 0,
+            case: None,
+            library: Library::from_csv(&library_path),
+            library_selected: 0,
+            drill: None,
+            solutions: vec![],
+            searching: None,
+            solution: None,
+            solving: None,
+            config_modified: CubeConfig::modified(),
+            file_scramble: config.scramble.clone(),
+            file_session: config.session.clone(),
+            profile_selected: 0,
+            settings: None,
+            history_report: Some(report).filter(|report| !report.is_empty()),
+            setting_selected: 0,
+            ask_cubes: false,
             config,
+        };
+        app.new_scrambles();
+        Ok(app)
+    }
+
+    pub fn next_scramble(&self) -> Scramble {
+        match self.config.session.mode {
+            Mode::Bld => Scramble::new_bld(self.config.scramble.length),
+            Mode::Fmc => Scramble::new_fmc(self.config.scramble.length),
+            _ => Scramble::new_rand(self.config.scramble.length),
+        }
+    }
+
+    /// Starts a multi-blind session with `cubes` scrambles shown up front
+    fn start_multi(&mut self, cubes: usize) -> Result<(), String> {
+        if cubes < 2 {
+            return Err(String::from("Enter at least 2 cubes"));
+        }
+        self.config.session.mode = Mode::MultiBld;
+        self.scrambles = (0..cubes)
+            .map(|_| Scramble::new_bld(self.config.scramble.length))
+            .collect();
+        self.scramble = self.scrambles[0].clone();
+        Ok(())
+    }
+
+    /// A multi-blind attempt can't start before its number of cubes is entered
+    fn missing_cubes(&self) -> bool {
+        self.config.session.mode == Mode::MultiBld && self.scrambles.is_empty()
+    }
+
+    /// Starts a relay with a scramble for each puzzle shown up front
+    fn start_relay(&mut self) {
+        let length = self.config.scramble.length;
+        self.scrambles = self
+            .config
+            .session
+            .relay
+            .iter()
+            .map(|puzzle| puzzle.scramble(length))
+            .collect();
+        self.scramble = match self.scrambles.first() {
+            Some(scramble) => scramble.clone(),
+            None => Scramble::new_rand(length),
+        };
+    }
+
+    /// Starts a competition simulation round, its scrambles are fixed up front
+    fn start_round(&mut self) {
+        self.round = self.history.next_round();
+        self.scrambles = (0..self.config.round.format.solves())
+            .map(|_| Scramble::new_rand(self.config.scramble.length))
+            .collect();
+        self.scramble = self.scrambles[0].clone();
+    }
+
+    /// Solves of the competition simulation round in progress
+    pub fn round_solves(&self) -> Vec<&Entry> {
+        self.history.round(self.round)
+    }
+
+    /// Moves on to the scramble of the next solve of the round
+    fn next_round_scramble(&mut self) {
+        let solves = self.round_solves().len();
+        if let Some(scramble) = self.scrambles.get(solves) {
+            self.scramble = scramble.clone();
+        }
+    }
+
+    /// Sets up a random case of the trainer's algorithm sets
+    fn start_case(&mut self) {
+        self.scrambles.clear();
+        self.case = trainer::random_case(&self.config.trainer.sets);
+        self.scramble = match self.case {
+            Some(case) => case.scramble(),
+            None => {
+                self.message = Some(String::from("No algorithm sets in [trainer]"));
+                self.next_scramble()
+            }
+        };
+    }
+
+    /// Takes the next scramble of the loaded set until it runs out
+    fn next_set_scramble(&mut self) -> Option<Scramble> {
+        let set = self.scramble_set.as_ref()?;
+        let scramble = set.scrambles.get(self.set_index).cloned();
+        match scramble {
+            Some(_) => self.set_index += 1,
+            None => {
+                self.message = Some(format!("Finished the scramble set {}", set.name));
+                self.scramble_set = None;
+            }
+        }
+        scramble
+    }
+
+    /// Replaces the scramble, or every scramble of a multi puzzle attempt
+    fn new_scrambles(&mut self) {
+        match self.config.session.mode {
+            // the number of cubes was checked when it was entered
+            Mode::MultiBld if !self.scrambles.is_empty() => {
+                let _ = self.start_multi(self.scrambles.len());
+            }
+            Mode::Relay => self.start_relay(),
+            Mode::CompSim => self.start_round(),
+            Mode::Trainer => self.start_case(),
+            _ => {
+                self.scrambles.clear();
+                self.scramble = match self.next_set_scramble() {
+                    Some(scramble) => scramble,
+                    None => self.next_scramble(),
+                };
+            }
+        }
+    }
+
+    /// Records the solve that just ended and moves on to the next scramble
+    fn record(&mut self, penalty: Penalty) {
+        let mode = self.config.session.mode;
+        self.history
+            .push(&self.timer, &self.scramble, penalty, mode);
+        match mode {
+            Mode::Relay => {
+                if let Some(entry) = self.history.last_mut() {
+                    entry.scrambles = self.scrambles.clone();
+                }
+                self.new_scrambles();
+            }
+            Mode::CompSim => {
+                if let Some(entry) = self.history.last_mut() {
+                    entry.round = Some(self.round);
+                }
+                if self.config.round.is_over(&self.round_solves()) {
+                    self.state = AppState::RoundSummary;
+                } else {
+                    self.next_round_scramble();
+                }
+            }
+            // the case is only named once it has been solved
+            Mode::Trainer => {
+                if let (Some(entry), Some(case)) = (self.history.last_mut(), self.case) {
+                    entry.case = Some(case.id());
+                    self.message = Some(format!("That was {}", case.id()));
+                }
+                self.new_scrambles();
+            }
+            _ => self.new_scrambles(),
+        }
+    }
+
+    /// Time left of the fewest moves attempt in progress
+    pub fn fmc_remaining(&self) -> Duration {
+        FMC_TIME_LIMIT.saturating_sub(self.timer.elapsed())
+    }
+
+    /// Checks the typed in solution on the scrambled cube and records it
+    fn submit_fmc(&mut self) -> Result<(), String> {
+        let solution = self
+            .input
+            .parse::<Reconstruction>()
+            .map_err(|err| err.to_string())?;
+        // as in WCA fewest moves, where a slice would be two moves anyway
+        if solution.0.iter().any(|m| m.m.layers == Layers::Slice) {
+            return Err(String::from(
+                "Slice moves aren't allowed, use outer or wide moves",
+            ));
+        }
+        let mut cube = Cube::scrambled(&self.scramble);
+        cube.apply_all(&solution.0.iter().map(|m| m.m).collect::<Vec<Move>>());
+        if !cube.is_solved() {
+            return Err(String::from("Solution doesn't solve the cube"));
+        }
+        self.timer.stop_at(self.timer.elapsed().min(FMC_TIME_LIMIT));
+        self.history
+            .push(&self.timer, &self.scramble, Penalty::No, Mode::Fmc);
+        if let Some(entry) = self.history.last_mut() {
+            entry.moves = Some(solution);
+        }
+        self.end_fmc();
+        Ok(())
+    }
+
+    fn give_up_fmc(&mut self) {
+        self.timer.stop_at(self.timer.elapsed().min(FMC_TIME_LIMIT));
+        self.history
+            .push(&self.timer, &self.scramble, Penalty::DNF, Mode::Fmc);
+        self.end_fmc();
+    }
+
+    fn end_fmc(&mut self) {
+        self.input.clear();
+        self.input_error = None;
+        self.new_scrambles();
+        self.state = AppState::Idle;
+    }
+
+    /// Checks the typed in step on the scrambled cube and records it with the
+    /// move count of its optimal solutions
+    fn submit_plan(&mut self) -> Result<(), String> {
+        let goal = self.config.plan.goal;
+        let planned = self
+            .input
+            .parse::<Reconstruction>()
+            .map_err(|err| err.to_string())?;
+        let mut cube = Cube::scrambled(&self.scramble);
+        cube.apply_all(&planned.0.iter().map(|m| m.m).collect::<Vec<Move>>());
+        if !goal.is_solved(&cube) {
+            return Err(format!("Solution doesn't solve the {}", goal));
+        }
+        self.timer.stop();
+        self.history
+            .push(&self.timer, &self.scramble, Penalty::No, Mode::Plan);
+        if let Some(entry) = self.history.last_mut() {
+            entry.moves = Some(planned);
+        }
+        let cube = Cube::scrambled(&self.scramble);
+        let max = self.config.plan.solutions.max(1);
+        self.solutions.clear();
+        self.searching = Some(spawn_search(move || goal.solutions(&cube, max)));
+        self.state = AppState::PlanResult;
+        Ok(())
+    }
+
+    /// Records the optimal solutions of the planned step once found, the
+    /// planned solve being the last entry until the result is left
+    fn planned(&mut self, solutions: Vec<Scramble>) {
+        if let Some(entry) = self.history.last_mut() {
+            entry.optimal = solutions.first().map(|s| s.moves().len());
         }
+        self.solutions = solutions;
+    }
+
+    /// Picks up the results of the searches running on their own thread
+    fn poll_searches(&mut self) {
+        if let Some(searching) = &self.searching {
+            match searching.try_recv() {
+                Ok(solutions) => {
+                    self.searching = None;
+                    self.planned(solutions);
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    self.searching = None;
+                    self.message = Some(String::from("The search for optimal solutions failed"));
+                }
+            }
+        }
+        if let Some(solving) = &self.solving {
+            match solving.try_recv() {
+                Ok(solution) => {
+                    self.solving = None;
+                    self.solution = Some(solution);
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    self.solving = None;
+                    self.message = Some(String::from("The solver failed"));
+                    self.state = AppState::Detail;
+                }
+            }
+        }
+    }
+
+    fn save_library(&mut self) {
+        let saved = CubeConfig::get_library_path()
+            .map_err(|err| err.to_string())
+            .and_then(|path| self.library.save_csv(&path).map_err(|err| err.to_string()));
+        if let Err(err) = saved {
+            self.message = Some(format!("Failed to save the algorithm library: {}", err));
+        }
+    }
+
+    /// Why new solves aren't saved, shown for as long as the history is
+    /// read-only
+    pub fn read_only_notice(&self) -> Option<String> {
+        self.history.read_only().map(|version| {
+            format!(
+                "History is read-only (v{}), new solves won't be saved",
+                version
+            )
+        })
+    }
+
+    /// Saves the history, returning why it couldn't be
+    fn save_history(&self) -> Result<(), ConfigError> {
+        let path = CubeConfig::get_history_path()?;
+        self.history
+            .save_csv(&path)
+            .map_err(|err| ConfigError::Io { path, err })
+    }
+
+    /// Sets up the most overdue case of the library, back to the library
+    /// once every due case has been drilled
+    fn next_drill(&mut self) {
+        self.timer.reset();
+        self.drill = self.library.due().first().map(|&case| Drill {
+            case,
+            setup: self.library.cases()[case].scramble(),
+        });
+        self.state = match self.drill {
+            Some(_) => AppState::Drill,
+            None => {
+                self.message = Some(String::from("No cases are due for a drill"));
+                AppState::Library
+            }
+        };
+    }
+
+    fn export_scrambles(&mut self) -> Result<String, ConfigError> {
+        let history_path = CubeConfig::get_history_path()?;
+        let path = Path::new(&history_path).with_file_name("multibld-scrambles.txt");
+        let text: String = self
+            .scrambles
+            .iter()
+            .enumerate()
+            .map(|(i, scramble)| format!("{}. {}\n", i + 1, scramble))
+            .collect();
+        let path = path.to_string_lossy().to_string();
+        match fs::write(&path, text) {
+            Ok(()) => Ok(path),
+            Err(err) => Err(ConfigError::Io { path, err }),
+        }
+    }
+
+    /// Handles the typed in text, returning a message when it isn't valid
+    fn submit(&mut self, prompt: Prompt) -> Result<(), String> {
+        match prompt {
+            Prompt::Time => {
+                let ManualEntry { time, penalty } = self
+                    .input
+                    .parse::<ManualEntry>()
+                    .map_err(|e| e.to_string())?;
+                self.history
+                    .push_time(time, &self.scramble, penalty, self.config.session.mode);
+                self.new_scrambles();
+            }
+            Prompt::Moves => {
+                let moves = self
+                    .input
+                    .parse::<Reconstruction>()
+                    .map_err(|err| err.to_string())?;
+                if let Some(entry) = self.history.get_mut(self.selected) {
+                    entry.moves = Some(moves).filter(|m| m.move_count() > 0);
+                }
+                self.state = AppState::Detail;
+            }
+            Prompt::Setting => {
+                let setting = &SETTINGS[self.setting_selected];
+                if let Some(settings) = self.settings.as_mut() {
+                    settings.set(setting, &self.input)?;
+                }
+                self.settings_saved();
+                self.state = AppState::Settings;
+            }
+            Prompt::Profile => {
+                let name = self.input.trim().to_string();
+                CubeConfig::check_profile(&name)?;
+                self.switch_profile(&name)?;
+            }
+            Prompt::Cubes => {
+                let cubes = self
+                    .input
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| "Enter at least 2 cubes")?;
+                self.start_multi(cubes)?;
+                self.state = AppState::Idle;
+            }
+            Prompt::SolvedCubes => {
+                let attempted = self.scrambles.len();
+                let solved = self
+                    .input
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&solved| solved <= attempted)
+                    .ok_or_else(|| format!("Enter a number of cubes from 0 to {}", attempted))?;
+                let multi = MultiBld { solved, attempted };
+                let penalty = if multi.is_dnf() {
+                    Penalty::DNF
+                } else {
+                    Penalty::No
+                };
+                self.history.push(
+                    &self.timer,
+                    &self.scramble,
+                    penalty,
+                    self.config.session.mode,
+                );
+                if let Some(entry) = self.history.last_mut() {
+                    entry.scrambles = self.scrambles.clone();
+                    entry.multi = Some(multi);
+                }
+                self.new_scrambles();
+                self.state = AppState::Idle;
+            }
+            Prompt::Plan => self.submit_plan()?,
+            Prompt::Alg => {
+                let case = self.input.parse::<LibraryCase>()?;
+                self.library_selected = self.library.add(case);
+                self.save_library();
+                self.state = AppState::Library;
+            }
+        }
+        Ok(())
     }
 
     pub fn on_key<B: Backend>(&mut self, key: KeyEvent, terminal: &mut Terminal<B>) {
+        self.message = None;
         if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL {
             self.state = AppState::ShouldQuit;
             return;
         }
+        if self.history_report.take().is_some() {
+            return;
+        }
         match self.state {
-            AppState::ShowHelp => {
-                if key.code == KeyCode::Char('q') {
-                    self.state = AppState::Idle;
-                }
-            }
+            AppState::ShowHelp if key.code == KeyCode::Char('q') => self.state = AppState::Idle,
             AppState::Idle => match key.code {
                 KeyCode::F(1) | KeyCode::Char('?') | KeyCode::Char('h') => {
                     self.state = AppState::ShowHelp
                 }
-                KeyCode::Char('q') => {
-                    self.state = AppState::ShouldQuit;
-                    self.history
-                        .save_csv(&CubeConfig::get_history_path().unwrap());
-                }
+                // the history is only left unsaved with Ctrl-C
+                KeyCode::Char('q') => match self.save_history() {
+                    Ok(()) => self.state = AppState::ShouldQuit,
+                    Err(err) => {
+                        self.message = Some(format!(
+                            "Failed to save the history, Ctrl-C quits without saving: {}",
+                            err
+                        ))
+                    }
+                },
                 KeyCode::Char('c') => self.state = AppState::Confirm("clear"),
-                KeyCode::Char('s') => self
-                    .history
-                    .save_csv(&CubeConfig::get_history_path().unwrap()),
-                KeyCode::Char('r') => {
-                    self.scramble = Scramble::new_rand(self.config.scramble.length)
+                KeyCode::Char('s') => {
+                    self.message = Some(match self.save_history() {
+                        Ok(()) => self
+                            .read_only_notice()
+                            .unwrap_or_else(|| String::from("History saved")),
+                        Err(err) => format!("Failed to save the history: {}", err),
+                    })
                 }
+                KeyCode::Char('r') => self.new_scrambles(),
                 KeyCode::Char('x') => self.state = AppState::Confirm("pop"),
-                KeyCode::Char('u') => self.history.undo_pop(),
+                KeyCode::Char('u') => {
+                    self.history.undo_pop();
+                    if self.config.session.mode == Mode::CompSim {
+                        self.next_round_scramble();
+                    }
+                }
                 KeyCode::Char('d') => self.state = AppState::Confirm("dnf"),
                 KeyCode::Char('t') => self.state = AppState::Confirm("time"),
+                KeyCode::Char('i') => self.state = AppState::Typing(Prompt::Time),
+                KeyCode::Char('m') => self.state = AppState::Typing(Prompt::Cubes),
+                KeyCode::Char('e') if !self.scrambles.is_empty() => {
+                    self.message = Some(match self.export_scrambles() {
+                        Ok(path) => format!("Scrambles exported to {}", path),
+                        Err(err) => format!("Failed to export scrambles: {}", err),
+                    })
+                }
+                KeyCode::Char('z') => self.cube = Cube::default(),
+                KeyCode::Char('p') => {
+                    self.config.session.phases = self.config.session.phases % MAX_PHASES + 1
+                }
+                KeyCode::Char('g') => self.state = AppState::Library,
+                KeyCode::Char('S') => match Settings::load() {
+                    Ok(settings) => {
+                        self.settings = Some(settings);
+                        self.state = AppState::Settings;
+                    }
+                    Err(err) => self.message = Some(err),
+                },
+                KeyCode::Char('P') => {
+                    let profile = CubeConfig::profile();
+                    self.profile_selected = CubeConfig::profiles()
+                        .iter()
+                        .position(|name| *name == profile)
+                        .unwrap_or(0);
+                    self.state = AppState::Profiles;
+                }
+                KeyCode::Char('v') if !self.history.entries().is_empty() => {
+                    self.selected = self.history.entries().len() - 1;
+                    self.state = AppState::Detail;
+                }
+                KeyCode::Char('b')
+                | KeyCode::Char('f')
+                | KeyCode::Char('l')
+                | KeyCode::Char('w')
+                | KeyCode::Char('a')
+                | KeyCode::Char('o') => {
+                    let mode = match key.code {
+                        KeyCode::Char('b') => Mode::Bld,
+                        KeyCode::Char('f') => Mode::Fmc,
+                        KeyCode::Char('l') => Mode::Relay,
+                        KeyCode::Char('a') => Mode::Trainer,
+                        KeyCode::Char('o') => Mode::Plan,
+                        _ => Mode::CompSim,
+                    };
+                    // the key of the active mode switches back to normal
+                    self.config.session.mode = match self.config.session.mode {
+                        active if active == mode => Mode::Normal,
+                        _ => mode,
+                    };
+                    self.scrambles.clear();
+                    self.new_scrambles();
+                }
+                KeyCode::Char(' ') if self.missing_cubes() => {
+                    self.state = AppState::Typing(Prompt::Cubes);
+                }
+                KeyCode::Char(' ') if self.config.session.mode == Mode::Fmc => {
+                    self.input.clear();
+                    self.input_error = None;
+                    self.timer.start();
+                    self.state = AppState::Fmc;
+                }
+                // planning time runs until the step is typed in
+                KeyCode::Char(' ') if self.config.session.mode == Mode::Plan => {
+                    self.input.clear();
+                    self.input_error = None;
+                    self.timer.start();
+                    self.state = AppState::Typing(Prompt::Plan);
+                }
                 KeyCode::Char(' ') => {
-                    if key.modifiers == KeyModifiers::CONTROL {
+                    if key.modifiers == KeyModifiers::CONTROL
+                        || !matches!(
+                            self.config.session.mode,
+                            Mode::Normal | Mode::Relay | Mode::CompSim
+                        )
+                    {
                         self.timer.start();
                         self.state = AppState::Timer;
                     } else {
@@ -94,16 +770,14 @@ impl<'a> App<'a> {
                 }
                 _ => {}
             },
-            AppState::Inspecting => {
-                if key.code == KeyCode::Char(' ') {
-                    #[cfg(feature = "debug")]
-                    tracing::info!("Starting KeyHold");
-                    self.key_hold.start();
-                    self.state = AppState::KeyHold;
-                    // self.state = AppState::Timer;
-                    // self.countdown.stop();
-                    // self.timer.start();
-                }
+            AppState::Inspecting if key.code == KeyCode::Char(' ') => {
+                #[cfg(feature = "debug")]
+                tracing::info!("Starting KeyHold");
+                self.key_hold.start();
+                self.state = AppState::KeyHold;
+                // self.state = AppState::Timer;
+                // self.countdown.stop();
+                // self.timer.start();
             }
             AppState::KeyHold => {
                 #[cfg(feature = "debug")]
@@ -138,43 +812,436 @@ impl<'a> App<'a> {
                 self.countdown.stop();
                 self.timer.start();
             }
+            // splits between relay puzzles are optional, <Space> always stops
+            AppState::Timer
+                if self.timer.splits.len() + 1 < self.config.session.phases()
+                    && !(self.config.session.mode == Mode::Relay
+                        && key.code == KeyCode::Char(' ')) =>
+            {
+                self.timer.split()
+            }
+            AppState::Timer if self.config.session.mode == Mode::MultiBld => {
+                self.timer.stop();
+                self.state = AppState::Typing(Prompt::SolvedCubes);
+            }
             AppState::Timer => {
                 self.state = AppState::Idle;
                 self.timer.stop();
-                self.history.push(&self.timer, &self.scramble, Penalty::No);
-                self.scramble = Scramble::new_rand(self.config.scramble.length);
+                self.record(Penalty::No);
             }
-            AppState::Confirm(s) => {
-                match s {
-                    "pop" => {
-                        if key.code == KeyCode::Char('y') {
-                            self.history.pop();
-                        }
+            AppState::Typing(prompt) => match key.code {
+                // the attempt is over, its result has to be recorded
+                KeyCode::Esc if prompt == Prompt::SolvedCubes => {}
+                KeyCode::Esc => {
+                    self.input.clear();
+                    self.input_error = None;
+                    if prompt == Prompt::Plan {
+                        self.timer.reset();
                     }
-                    "dnf" => {
-                        if key.code == KeyCode::Char('y') {
-                            self.history.penalize_last(Penalty::DNF);
-                        }
+                    // a multi-blind session can't go on without cubes
+                    if self.missing_cubes() {
+                        self.config.session.mode = Mode::Normal;
+                    }
+                    self.state = match prompt {
+                        Prompt::Moves => AppState::Detail,
+                        Prompt::Alg => AppState::Library,
+                        Prompt::Profile => AppState::Profiles,
+                        Prompt::Setting => AppState::Settings,
+                        _ => AppState::Idle,
+                    };
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Enter => match self.submit(prompt) {
+                    Ok(()) => {
+                        self.input.clear();
+                        self.input_error = None;
                     }
-                    "time" => {
-                        if key.code == KeyCode::Char('y') {
-                            self.history.penalize_last(Penalty::Time);
+                    Err(err) => self.input_error = Some(err),
+                },
+                KeyCode::Char(c) => self.input.push(c),
+                _ => {}
+            },
+            AppState::Fmc => match key.code {
+                KeyCode::Char('d') if key.modifiers == KeyModifiers::CONTROL => self.give_up_fmc(),
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Enter => {
+                    if let Err(err) = self.submit_fmc() {
+                        self.input_error = Some(err);
+                    }
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                _ => {}
+            },
+            AppState::RoundSummary => {
+                self.start_round();
+                self.state = AppState::Idle;
+            }
+            // the search result is recorded in the planned solve, only Esc
+            // leaves before it is found
+            AppState::PlanResult if self.searching.is_some() && key.code != KeyCode::Esc => {}
+            AppState::PlanResult => {
+                self.searching = None;
+                self.new_scrambles();
+                self.state = AppState::Idle;
+            }
+            AppState::Library => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.state = AppState::Idle,
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.library_selected = self.library_selected.saturating_sub(1)
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.library_selected = (self.library_selected + 1)
+                        .min(self.library.cases().len().saturating_sub(1))
+                }
+                KeyCode::Char('n') => {
+                    self.input.clear();
+                    self.state = AppState::Typing(Prompt::Alg);
+                }
+                KeyCode::Char('e') => {
+                    if let Some(case) = self.library.cases().get(self.library_selected) {
+                        self.input = case.to_string();
+                        self.state = AppState::Typing(Prompt::Alg);
+                    }
+                }
+                KeyCode::Char('x') if !self.library.cases().is_empty() => {
+                    self.state = AppState::Confirm("delete case")
+                }
+                KeyCode::Char('i') => {
+                    let added: usize = self
+                        .config
+                        .trainer
+                        .sets
+                        .iter()
+                        .map(|&set| self.library.import(set))
+                        .sum();
+                    self.message = Some(format!("Imported {} cases", added));
+                    self.save_library();
+                }
+                KeyCode::Char('d') => self.next_drill(),
+                _ => {}
+            },
+            AppState::Drill => match key.code {
+                _ if self.timer.state == TimerState::Active => {
+                    self.timer.stop();
+                    self.state = AppState::Recall;
+                }
+                KeyCode::Char(' ') => self.timer.start(),
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    self.drill = None;
+                    self.state = AppState::Library;
+                }
+                _ => {}
+            },
+            AppState::Recall => {
+                let recall = match key.code {
+                    KeyCode::Char('1') => Recall::Forgot,
+                    KeyCode::Char('2') => Recall::Hard,
+                    KeyCode::Char('3') => Recall::Good,
+                    KeyCode::Char('4') => Recall::Easy,
+                    _ => return,
+                };
+                if let Some(drill) = &self.drill {
+                    let target = Duration::from_secs(self.config.library.target_time);
+                    self.library
+                        .review(drill.case, recall, self.timer.result, target);
+                    self.save_library();
+                }
+                self.next_drill();
+            }
+            AppState::Detail => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.state = AppState::Idle,
+                KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.selected = (self.selected + 1).min(self.history.entries().len() - 1)
+                }
+                KeyCode::Char('m') => self.state = AppState::Typing(Prompt::Moves),
+                KeyCode::Char('s') => match self.history.entries().get(self.selected) {
+                    Some(entry) if !entry.is_single_3x3() => {
+                        self.message = Some(format!(
+                            "The solver only solves 3x3, not {} solves",
+                            entry.mode
+                        ))
+                    }
+                    Some(entry) => {
+                        let scramble = entry.scramble.clone();
+                        self.solution = None;
+                        self.solving = Some(spawn_search(move || {
+                            solver::solve(&scramble, solver::SOLVE_TIME)
+                        }));
+                        self.state = AppState::Solution;
+                    }
+                    None => {}
+                },
+                _ => {}
+            },
+            AppState::Solution => {
+                self.solving = None;
+                self.state = AppState::Detail;
+            }
+            AppState::Settings => {
+                let setting = &SETTINGS[self.setting_selected];
+                let result = match (key.code, self.settings.as_mut()) {
+                    (KeyCode::Char('q') | KeyCode::Esc, _) => {
+                        self.settings = None;
+                        self.state = AppState::Idle;
+                        return;
+                    }
+                    (KeyCode::Char('k') | KeyCode::Up, _) => {
+                        self.setting_selected = self.setting_selected.saturating_sub(1);
+                        return;
+                    }
+                    (KeyCode::Char('j') | KeyCode::Down, _) => {
+                        self.setting_selected = (self.setting_selected + 1).min(SETTINGS.len() - 1);
+                        return;
+                    }
+                    (KeyCode::Char('h') | KeyCode::Left | KeyCode::Char('-'), Some(settings)) => {
+                        settings.step(setting, -1)
+                    }
+                    (KeyCode::Char('l') | KeyCode::Right | KeyCode::Char('+'), Some(settings)) => {
+                        settings.step(setting, 1)
+                    }
+                    (KeyCode::Char('x'), Some(settings)) => settings.unset(setting),
+                    (KeyCode::Enter, Some(settings)) => match setting.kind {
+                        Kind::Toggle | Kind::Choice(_) => settings.step(setting, 1),
+                        _ => {
+                            self.input = settings.get(setting).unwrap_or_default();
+                            self.input_error = None;
+                            self.state = AppState::Typing(Prompt::Setting);
+                            return;
+                        }
+                    },
+                    _ => return,
+                };
+                match result {
+                    Ok(()) => self.settings_saved(),
+                    Err(err) => self.message = Some(err),
+                }
+            }
+            AppState::Profiles => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.state = AppState::Idle,
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.profile_selected = self.profile_selected.saturating_sub(1)
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.profile_selected =
+                        (self.profile_selected + 1).min(CubeConfig::profiles().len() - 1)
+                }
+                KeyCode::Char('n') => {
+                    self.input.clear();
+                    self.state = AppState::Typing(Prompt::Profile);
+                }
+                KeyCode::Enter => {
+                    if let Some(name) = CubeConfig::profiles().get(self.profile_selected) {
+                        if let Err(err) = self.switch_profile(name) {
+                            self.message = Some(err);
                         }
                     }
-                    "clear" => {
-                        if key.code == KeyCode::Char('y') {
+                }
+                _ => {}
+            },
+            AppState::Confirm(s) => {
+                if key.code == KeyCode::Char('y') {
+                    match s {
+                        "delete case" => {
+                            self.library.remove(self.library_selected);
+                            self.library_selected = self.library_selected.saturating_sub(1);
+                            self.save_library();
+                        }
+                        "pop" => {
+                            self.history.pop();
+                            if self.config.session.mode == Mode::CompSim {
+                                self.next_round_scramble();
+                            }
+                        }
+                        "dnf" => self.history.penalize_last(Penalty::DNF),
+                        "time" => self.history.penalize_last(Penalty::Time),
+                        "clear" => {
                             self.timer.reset();
                             self.history.clear();
                         }
+                        _ => {}
                     }
-                    _ => {}
                 }
+                self.state = match s {
+                    "delete case" => AppState::Library,
+                    _ => AppState::Idle,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    pub fn on_event<B: Backend>(&mut self, event: InputEvent, terminal: &mut Terminal<B>) {
+        match event {
+            InputEvent::Key(key) => self.on_key(key, terminal),
+            InputEvent::Stackmat(packet) => self.on_stackmat(packet),
+            InputEvent::CubeMove(m) => self.on_cube_move(m),
+        }
+    }
+
+    /// Smart cube has been scrambled to match the current scramble
+    pub fn cube_ready(&self) -> bool {
+        self.cube == Cube::scrambled(&self.scramble)
+    }
+
+    /// The first move after the cube matches the scramble starts the timer,
+    /// solving the cube stops it.
+    pub fn on_cube_move(&mut self, m: Move) {
+        let ready = self.cube_ready();
+        self.cube.apply(&m);
+        match self.state {
+            AppState::Idle | AppState::Inspecting | AppState::KeyHold
+                if ready && !self.missing_cubes() =>
+            {
+                self.countdown.stop();
+                self.key_hold.stop();
+                self.timer.start();
+                self.state = AppState::Timer;
+                self.solve_moves = Reconstruction::default();
+                self.solve_moves.push(m, Some(Duration::from_secs(0)));
+            }
+            AppState::Timer => {
+                self.solve_moves.push(m, Some(self.timer.elapsed()));
+                if self.cube.is_solved() {
+                    self.state = AppState::Idle;
+                    self.timer.stop();
+                    self.record(Penalty::No);
+                    if let Some(entry) = self.history.last_mut() {
+                        entry.moves = Some(std::mem::take(&mut self.solve_moves));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn on_stackmat(&mut self, packet: Packet) {
+        match (packet.status, &self.state) {
+            (Status::Running, AppState::Idle | AppState::Inspecting | AppState::KeyHold)
+                if !self.missing_cubes() =>
+            {
+                self.countdown.stop();
+                self.key_hold.stop();
+                self.timer.start_from(packet.time);
+                self.state = AppState::Timer;
+            }
+            (Status::Stopped, AppState::Timer) => {
                 self.state = AppState::Idle;
+                self.timer.stop_at(packet.time);
+                self.record(Penalty::No);
             }
+            (Status::Idle, AppState::Idle) => self.timer.reset(),
             _ => {}
         }
     }
 
+    /// Reloads the config file once it changes, keeping the old config when
+    /// the new one isn't valid. Stackmat and smart cube sources stay the ones
+    /// the app started with.
+    fn reload_config(&mut self) {
+        let modified = CubeConfig::modified();
+        if modified == self.config_modified {
+            return;
+        }
+        self.config_modified = modified;
+        match CubeConfig::new() {
+            Ok(config) => {
+                self.apply_config(config);
+                self.message = Some(String::from("Reloaded the config"));
+            }
+            Err(err) => self.message = Some(format!("{}, keeping the old config", err)),
+        }
+    }
+
+    /// Applies the setting just written to the config file
+    fn settings_saved(&mut self) {
+        self.reload_config();
+        self.message = Some(format!("Saved {}", SETTINGS[self.setting_selected].name()));
+    }
+
+    /// Switches to the config, history and library of `profile`, creating it
+    /// if new, after saving the history of the current one
+    fn switch_profile(&mut self, profile: &str) -> Result<(), String> {
+        self.save_history()
+            .map_err(|err| format!("Failed to save the history: {}", err))?;
+        let previous = CubeConfig::profile();
+        CubeConfig::set_profile(profile);
+        let config = match CubeConfig::migrate().and_then(|_| CubeConfig::new()) {
+            Ok(config) => config,
+            Err(err) => {
+                CubeConfig::set_profile(&previous);
+                return Err(err.to_string());
+            }
+        };
+        let paths = CubeConfig::get_history_path()
+            .and_then(|history| CubeConfig::get_library_path().map(|library| (history, library)));
+        let (history_path, library_path) = match paths {
+            Ok(paths) => paths,
+            Err(err) => {
+                CubeConfig::set_profile(&previous);
+                return Err(err.to_string());
+            }
+        };
+        let (history, report) = History::from_csv(&history_path);
+        self.history = history;
+        self.history_report = Some(report).filter(|report| !report.is_empty());
+        self.library = Library::from_csv(&library_path);
+        self.selected = 0;
+        self.library_selected = 0;
+        self.config_modified = CubeConfig::modified();
+        self.state = AppState::Idle;
+        self.apply_config(config);
+        self.message = Some(format!("Switched to the {} profile", profile));
+        Ok(())
+    }
+
+    /// Uses `config` instead of the current one, rebuilding what depends on it.
+    /// The mode and phases picked with keys are kept unless they changed in
+    /// the file since it was last loaded.
+    fn apply_config(&mut self, mut config: CubeConfig) {
+        config.stackmat = self.config.stackmat.take();
+        config.smartcube = self.config.smartcube.take();
+        let file_scramble = std::mem::replace(&mut self.file_scramble, config.scramble.clone());
+        let file_session = std::mem::replace(&mut self.file_session, config.session.clone());
+        if config.session.mode == file_session.mode {
+            config.session.mode = self.config.session.mode;
+        }
+        if config.session.phases == file_session.phases {
+            config.session.phases = self.config.session.phases;
+        }
+        let old = std::mem::replace(&mut self.config, config);
+
+        self.countdown = Countdown::new(Duration::from_secs(self.config.inspection.length as u64));
+        self.key_hold = Countdown::new(Duration::from_secs(self.config.inspection.key_hold as u64));
+        scramble::min_moves(self.config.scramble.min_moves);
+        let scramble = &self.config.scramble;
+        if scramble.seed != file_scramble.seed {
+            if let Some(seed) = scramble.seed {
+                scramble::seed(seed);
+            }
+        }
+        if scramble.length != file_scramble.length
+            || scramble.seed != file_scramble.seed
+            || scramble.min_moves != file_scramble.min_moves
+            || self.config.session.mode != old.session.mode
+        {
+            self.scrambles.clear();
+            self.new_scrambles();
+        }
+        // also asked after picking a profile at startup, the settings screen
+        // is left first
+        if self.missing_cubes() {
+            match self.state {
+                AppState::Idle => self.state = AppState::Typing(Prompt::Cubes),
+                _ => self.ask_cubes = true,
+            }
+        }
+    }
+
     pub fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
         let mut last_tick = Instant::now();
         loop {
@@ -186,9 +1253,13 @@ impl<'a> App<'a> {
                 .unwrap_or_else(|| Duration::from_millis(100));
             if crossterm::event::poll(timeout)? {
                 if let Event::Key(key) = event::read()? {
-                    self.on_key(key, terminal)
+                    self.on_event(InputEvent::Key(key), terminal)
                 }
             }
+            while let Ok(event) = self.events.try_recv() {
+                self.on_event(event, terminal);
+            }
+            self.poll_searches();
             if (self.state == AppState::Inspecting || self.state == AppState::KeyHold)
                 && self.countdown.done()
             {
@@ -202,11 +1273,41 @@ impl<'a> App<'a> {
                 }
                 self.state = AppState::Idle;
                 self.countdown.stop();
-                self.history.push(&self.timer, &self.scramble, Penalty::DNS);
-                self.scramble = Scramble::new_rand(self.config.scramble.length);
+                self.record(Penalty::DNS);
+            }
+            if self.state == AppState::Timer && self.config.session.mode == Mode::MultiBld {
+                let limit = MultiBld::time_limit(self.scrambles.len());
+                if self.timer.elapsed() >= limit {
+                    self.timer.stop_at(limit);
+                    self.state = AppState::Typing(Prompt::SolvedCubes);
+                }
+            }
+            if self.state == AppState::Timer && self.config.session.mode == Mode::CompSim {
+                let limit = self.config.round.limit(&self.round_solves());
+                if let Some(limit) = limit.filter(|&limit| self.timer.elapsed() >= limit) {
+                    self.timer.stop_at(limit);
+                    self.state = AppState::Idle;
+                    self.record(Penalty::DNF);
+                }
+            }
+            if self.state == AppState::Fmc && self.fmc_remaining().is_zero() {
+                // whatever is written down when time runs out counts
+                if self.submit_fmc().is_err() {
+                    self.give_up_fmc();
+                }
+            }
+            if self.ask_cubes && self.state == AppState::Idle {
+                self.ask_cubes = false;
+                if self.missing_cubes() {
+                    self.state = AppState::Typing(Prompt::Cubes);
+                }
             }
             if last_tick.elapsed() >= self.tick_rate {
                 last_tick = Instant::now();
+                // a solve in progress keeps the config it started with
+                if self.state == AppState::Idle {
+                    self.reload_config();
+                }
             }
             if self.state == AppState::ShouldQuit {
                 return Ok(());
@@ -214,3 +1315,13 @@ impl<'a> App<'a> {
         }
     }
 }
+
+/// Runs a search on its own thread like the input sources so the UI stays
+/// responsive, `run` picks up the result from the receiver
+fn spawn_search<T: Send + 'static>(search: impl FnOnce() -> T + Send + 'static) -> Receiver<T> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(search());
+    });
+    rx
+}
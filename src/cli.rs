@@ -0,0 +1,190 @@
+use std::env;
+
+use crate::{
+    config::CubeConfig,
+    input::{
+        smartcube::{Protocol, SmartCubeConfig},
+        stackmat::{Format, StackmatConfig},
+    },
+    scramble::Puzzle,
+    scramble::{ParseMoveError, Scramble},
+    scramble_set::ScrambleSet,
+};
+
+pub const USAGE: &str = "Usage: cursed-timer [OPTIONS]
+       cursed-timer stackmat-replay [--serial] [--sample-rate <HZ>] <FILE | ->
+       cursed-timer scramble-set [--puzzle <PUZZLE>] [--count <N>] [--length <N>] [--seed <SEED>] <FILE>
+       cursed-timer solve <SCRAMBLE>
+
+Options:
+    --stackmat <DEVICE>      Read a Stackmat timer from a serial device (1200 8N1)
+    --stackmat-pcm <FILE>    Read a Stackmat timer from raw S16_LE mono PCM
+    --sample-rate <HZ>       Sample rate of the PCM stream [default: 44100]
+    --smartcube <SOURCE>     Read smart cube moves from a file, pipe or tcp://host:port
+    --protocol <PROTOCOL>    Smart cube protocol: text, gan, giiker or moyu [default: text]
+    --seed <SEED>            Generate the same scramble sequence as everyone using SEED
+    --scrambles <FILE>       Use the scrambles of a file in order, one per line or a TNoodle JSON export
+    --profile <NAME>         Use the config and history of a profile, created if new [default: pick one]
+    -h, --help               Print this help
+
+Commands:
+    stackmat-replay          Print the Stackmat packets decoded from a recording, - for stdin
+    scramble-set             Write N scrambles for a puzzle (2x2 to 5x5) to a file to share,
+                             named after the file [default: 12 3x3 scrambles of 20 moves]
+    solve                    Print a short solution of a 3x3 scramble, and how CFOP and Roux
+                             solve it step by step";
+
+#[derive(Default)]
+pub struct Args {
+    pub stackmat: Option<StackmatConfig>,
+    pub smartcube: Option<SmartCubeConfig>,
+    pub seed: Option<u64>,
+    pub scrambles: Option<ScrambleSet>,
+    /// Profile to start with instead of picking one
+    pub profile: Option<String>,
+}
+
+pub struct ScrambleSetArgs {
+    pub path: String,
+    pub puzzle: Puzzle,
+    pub count: usize,
+    /// Moves per 3x3 scramble
+    pub length: usize,
+    pub seed: Option<u64>,
+}
+
+pub enum Command {
+    Run(Args),
+    StackmatReplay(StackmatConfig),
+    ScrambleSet(ScrambleSetArgs),
+    Solve(Scramble),
+    Help,
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("{} expects a value", flag))
+}
+
+fn sample_rate(args: &mut impl Iterator<Item = String>) -> Result<u32, String> {
+    let rate = value(args, "--sample-rate")?;
+    rate.parse()
+        .map_err(|_| format!("'{}' is not a valid sample rate", rate))
+}
+
+fn number<T: std::str::FromStr>(
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> Result<T, String> {
+    let n = value(args, flag)?;
+    n.parse()
+        .map_err(|_| format!("'{}' is not a valid number for {}", n, flag))
+}
+
+fn stackmat(source: String, format: Format) -> StackmatConfig {
+    StackmatConfig {
+        source,
+        format,
+        sample_rate: 44100,
+    }
+}
+
+/// Parses the command line arguments, returning a message for invalid ones
+pub fn parse() -> Result<Command, String> {
+    let mut args = env::args().skip(1).peekable();
+    if args.peek().map(String::as_str) == Some("stackmat-replay") {
+        args.next();
+        let mut format = Format::Pcm;
+        let mut rate = 44100;
+        let mut source = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--serial" => format = Format::Serial,
+                "--sample-rate" => rate = sample_rate(&mut args)?,
+                _ if source.is_none() => source = Some(arg),
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+        let mut config = stackmat(source.ok_or("stackmat-replay expects a FILE")?, format);
+        config.sample_rate = rate;
+        return Ok(Command::StackmatReplay(config));
+    }
+    if args.peek().map(String::as_str) == Some("scramble-set") {
+        args.next();
+        let mut puzzle = Puzzle::Cube3;
+        let mut count = 12;
+        let mut length = 20;
+        let mut seed = None;
+        let mut path = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--puzzle" => puzzle = value(&mut args, &arg)?.parse()?,
+                "--count" => count = number(&mut args, &arg)?,
+                "--length" => length = number(&mut args, &arg)?,
+                "--seed" => seed = Some(number(&mut args, &arg)?),
+                _ if path.is_none() => path = Some(arg),
+                _ => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+        return Ok(Command::ScrambleSet(ScrambleSetArgs {
+            path: path.ok_or("scramble-set expects a FILE")?,
+            puzzle,
+            count,
+            length,
+            seed,
+        }));
+    }
+    if args.peek().map(String::as_str) == Some("solve") {
+        args.next();
+        // the scramble may or may not be quoted
+        let scramble = args.collect::<Vec<String>>().join(" ");
+        if scramble.trim().is_empty() {
+            return Err(String::from("solve expects a SCRAMBLE"));
+        }
+        let scramble = scramble
+            .parse()
+            .map_err(|err: ParseMoveError| err.to_string())?;
+        return Ok(Command::Solve(scramble));
+    }
+
+    let mut parsed = Args::default();
+    let mut rate = None;
+    let mut protocol = Protocol::Text;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--stackmat" => {
+                parsed.stackmat = Some(stackmat(value(&mut args, &arg)?, Format::Serial))
+            }
+            "--stackmat-pcm" => {
+                parsed.stackmat = Some(stackmat(value(&mut args, &arg)?, Format::Pcm))
+            }
+            "--sample-rate" => rate = Some(sample_rate(&mut args)?),
+            "--smartcube" => {
+                parsed.smartcube = Some(SmartCubeConfig {
+                    source: value(&mut args, &arg)?,
+                    protocol,
+                })
+            }
+            "--protocol" => protocol = value(&mut args, &arg)?.parse()?,
+            "--seed" => parsed.seed = Some(number(&mut args, &arg)?),
+            "--profile" => {
+                let profile = value(&mut args, &arg)?;
+                CubeConfig::check_profile(&profile)?;
+                parsed.profile = Some(profile)
+            }
+            "--scrambles" => {
+                let set = ScrambleSet::load(&value(&mut args, &arg)?).map_err(|e| e.to_string())?;
+                parsed.scrambles = Some(set)
+            }
+            _ => return Err(format!("Unexpected argument '{}'", arg)),
+        }
+    }
+    if let (Some(config), Some(rate)) = (parsed.stackmat.as_mut(), rate) {
+        config.sample_rate = rate;
+    }
+    if let Some(config) = parsed.smartcube.as_mut() {
+        config.protocol = protocol;
+    }
+    Ok(Command::Run(parsed))
+}
@@ -1,14 +1,40 @@
-use dirs;
+use std::{
+    env,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str,
+    sync::RwLock,
+    time::SystemTime,
+};
 
-use std::{fs::File, io::Write};
-
-use config::Config;
 use config::File as ConfigFile;
+use config::{Config, FileFormat};
 use serde_derive::Deserialize;
 
-#[derive(Debug, Deserialize)]
+use crate::{
+    input::{smartcube::SmartCubeConfig, stackmat::StackmatConfig},
+    library::LibraryConfig,
+    plan::PlanConfig,
+    round::RoundConfig,
+    scramble::{Puzzle, MAX_MIN_MOVES},
+    trainer::TrainerConfig,
+};
+
+#[derive(Debug, Deserialize, Clone)]
 pub struct ScrambleConfig {
     pub length: usize,
+    /// Seed making the scramble sequence the same on every run
+    pub seed: Option<u64>,
+    /// Scramble set file to use instead of generating scrambles
+    pub file: Option<String>,
+    /// Scrambles solvable in fewer moves are rejected, up to MAX_MIN_MOVES
+    #[serde(default = "default_min_moves")]
+    pub min_moves: usize,
+}
+
+fn default_min_moves() -> usize {
+    2
 }
 
 #[derive(Debug, Deserialize)]
@@ -17,13 +43,245 @@ pub struct InspectionConfig {
     pub key_hold: usize,
 }
 
-const DEFAULT_CONFIG: &str = "[scramble]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Normal,
+    /// Blindfolded: no inspection, memo / execution split and Mo3 / Bo3 stats
+    Bld,
+    /// Multi-blind: several cubes in one attempt, scored by points then time
+    MultiBld,
+    /// Fewest moves: an hour to find the shortest solution, scored by moves
+    Fmc,
+    /// Several puzzles solved one after the other in a single timer run
+    Relay,
+    /// Competition simulation: rounds as configured in `[round]`
+    CompSim,
+    /// Algorithm trainer: setups of last layer cases, recognition / execution
+    /// split and per-case stats
+    Trainer,
+    /// Inspection practice: the first step typed in as planned, compared with
+    /// its optimal solutions
+    Plan,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Mode::Normal => "normal",
+            Mode::Bld => "bld",
+            Mode::MultiBld => "multibld",
+            Mode::Fmc => "fmc",
+            Mode::Relay => "relay",
+            Mode::CompSim => "compsim",
+            Mode::Trainer => "trainer",
+            Mode::Plan => "plan",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl str::FromStr for Mode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Mode::Normal),
+            "bld" => Ok(Mode::Bld),
+            "multibld" => Ok(Mode::MultiBld),
+            "fmc" => Ok(Mode::Fmc),
+            "relay" => Ok(Mode::Relay),
+            "compsim" => Ok(Mode::CompSim),
+            "trainer" => Ok(Mode::Trainer),
+            "plan" => Ok(Mode::Plan),
+            _ => Err(format!("'{}' is not a session mode", s)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub mode: Mode,
+    /// Number of phases each solve is split into, e.g. 3 for cross / F2L / LL
+    pub phases: usize,
+    pub phase_names: Vec<String>,
+    /// Hide the running time while solving
+    pub hide_timer: bool,
+    /// Puzzles of a relay in the order they are solved
+    pub relay: Vec<Puzzle>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            mode: Mode::Normal,
+            phases: 1,
+            phase_names: vec![],
+            hide_timer: false,
+            relay: vec![Puzzle::Cube2, Puzzle::Cube3, Puzzle::Cube4],
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Blindfolded solves are always split into memo and execution, trainer
+    /// cases into recognition and execution, relays into one phase per puzzle
+    pub fn phases(&self) -> usize {
+        match self.mode {
+            Mode::Bld | Mode::Trainer => 2,
+            Mode::Relay => self.relay.len(),
+            Mode::MultiBld | Mode::Fmc | Mode::Plan => 1,
+            _ => self.phases,
+        }
+    }
+
+    pub fn phase_name(&self, phase: usize) -> String {
+        match (self.mode, phase) {
+            (Mode::Bld, 0) => String::from("Memo"),
+            (Mode::Trainer, 0) => String::from("Recognition"),
+            (Mode::Bld | Mode::Trainer, _) => String::from("Execution"),
+            (Mode::Relay, _) => self
+                .relay
+                .get(phase)
+                .map(|puzzle| puzzle.to_string())
+                .unwrap_or_default(),
+            _ => self
+                .phase_names
+                .get(phase)
+                .cloned()
+                .unwrap_or_else(|| format!("Phase {}", phase + 1)),
+        }
+    }
+}
+
+pub const DEFAULT_CONFIG: &str = r#"[scramble]
 length = 16
+# seed = 42 # same scrambles in the same order for everyone using this seed
+# file = "scrambles.txt" # one scramble per line or a TNoodle JSON export
+# Reject scrambles that can be solved in fewer moves, at most 6
+min_moves = 2
 
 [inspection]
 length = 16
 key_hold = 2
-";
+
+[session]
+mode = "normal" # or "bld", "multibld", "fmc", "relay", "compsim", "trainer", "plan"
+# Press any key during a solve to record a split, e.g. cross / F2L / LL
+phases = 1
+# phase_names = ["Cross", "F2L", "LL"]
+hide_timer = false
+# Puzzles of a relay session, any of 2x2, 3x3, 4x4 and 5x5
+relay = ["2x2", "3x3", "4x4"]
+
+# Competition simulation rounds
+[round]
+format = "ao5" # or "mo3", "bo3"
+# time_limit = 600 # seconds per solve
+# cumulative_limit = 1200 # seconds for every solve of the round together
+# cutoff = 60 # seconds one of the first cutoff_solves solves has to beat
+cutoff_solves = 2
+
+# Algorithm trainer cases, any of "oll", "pll", "coll" and "zbll"
+[trainer]
+sets = ["pll"]
+
+# Inspection practice, any of "cross", "eocross", "firstblock" and "2x2x2"
+[plan]
+goal = "cross"
+# Optimal solutions shown next to the planned one
+solutions = 5
+
+# Algorithm library drills
+[library]
+# Seconds a drill may take before its recall counts as one step worse
+target_time = 3
+
+# Uncomment to drive the timer with a Stackmat
+# [stackmat]
+# source = "/dev/ttyUSB0"
+# format = "serial" # or "pcm" for a raw S16_LE mono recording / pipe
+# sample_rate = 44100
+
+# Uncomment to read moves from a smart cube through a bridge
+# [smartcube]
+# source = "tcp://127.0.0.1:5555" # or a file / pipe path
+# protocol = "gan" # text, gan, giiker or moyu
+"#;
+
+/// Why the config file couldn't be loaded
+#[derive(Debug)]
+pub enum ConfigError {
+    /// No home / app data directory to keep the config and history in
+    NoConfigDir,
+    /// The config file, the data directory, the history or a file moved from
+    /// where older versions kept it can't be created, read, written or moved
+    Io { path: String, err: io::Error },
+    /// The config file isn't valid TOML, or `key` doesn't hold a valid value
+    Invalid {
+        path: String,
+        key: Option<String>,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::NoConfigDir => {
+                write!(f, "No home directory to keep the config and history in")
+            }
+            ConfigError::Io { path, err } => write!(f, "Can't access {}: {}", path, err),
+            ConfigError::Invalid {
+                path,
+                key: Some(key),
+                reason,
+            } => write!(f, "Invalid config {}: {}: {}", path, key, reason),
+            ConfigError::Invalid { path, reason, .. } => {
+                write!(f, "Invalid config {}: {}", path, reason)
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl ConfigError {
+    fn invalid(path: &str, err: config::ConfigError) -> ConfigError {
+        let (key, reason) = match err {
+            config::ConfigError::FileParse { cause, .. } => (None, cause.to_string()),
+            config::ConfigError::Type {
+                unexpected,
+                expected,
+                key,
+                ..
+            } => (key, format!("expected {}, found {}", expected, unexpected)),
+            config::ConfigError::NotFound(key) => (Some(key), String::from("missing")),
+            err => (None, err.to_string()),
+        };
+        ConfigError::Invalid {
+            path: path.to_string(),
+            key,
+            reason,
+        }
+    }
+}
+
+/// Name of the profile using the config and data directories themselves
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profile whose config and history are used, the default one when None
+static PROFILE: RwLock<Option<String>> = RwLock::new(None);
+
+/// `var` if it is set to an absolute path as the XDG spec asks, else
+/// `fallback` in the home directory
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(fallback)))
+}
 
 /// Models the config file found in the app config directory
 /// see DEFAULT_CONFIG for example config.toml.
@@ -35,69 +293,266 @@ key_hold = 2
 pub struct CubeConfig {
     pub scramble: ScrambleConfig,
     pub inspection: InspectionConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub round: RoundConfig,
+    #[serde(default)]
+    pub trainer: TrainerConfig,
+    #[serde(default)]
+    pub library: LibraryConfig,
+    #[serde(default)]
+    pub plan: PlanConfig,
+    pub stackmat: Option<StackmatConfig>,
+    pub smartcube: Option<SmartCubeConfig>,
 }
 
 impl CubeConfig {
-    /// Returns the config directory used for the configuration.
-    /// @return Option<String>: the config directory
-    pub fn get_history_path() -> Option<String> {
+    /// Returns the directory of the config file: $CURSED_TIMER_CONFIG, or
+    /// cursed-timer in the XDG config directory.
+    /// @return Option<PathBuf>: the config directory
+    pub fn config_dir() -> Option<PathBuf> {
+        if let Some(dir) = env::var_os("CURSED_TIMER_CONFIG") {
+            return Some(PathBuf::from(dir));
+        }
+
         #[cfg(any(target_os = "macos", target_os = "linux"))]
         {
-            if let Some(home_dir) = dirs::home_dir() {
-                let config_path = home_dir.join(".config/cursed-timer/history.csv");
-                return Some(config_path.to_string_lossy().to_string());
+            if let Some(dir) = xdg_dir("XDG_CONFIG_HOME", ".config") {
+                return Some(dir.join("cursed-timer"));
             }
         }
 
         #[cfg(target_os = "windows")]
         {
             if let Some(data_local_dir) = dirs::data_local_dir() {
-                let config_path = data_local_dir.join("CursedTimer/history.csv");
-                return Some(config_path.to_string_lossy().to_string());
+                return Some(data_local_dir.join("CursedTimer"));
             }
         }
 
         None
     }
 
-    /// Returns the config directory used for the configuration.
-    /// @return Option<String>: the config directory path as a string, or None if not found
-    pub fn get_config_path() -> Option<String> {
+    /// Returns the directory of the history and the algorithm library:
+    /// $CURSED_TIMER_DATA, or cursed-timer in the XDG data directory.
+    /// @return Option<PathBuf>: the data directory
+    pub fn data_dir() -> Option<PathBuf> {
+        if let Some(dir) = env::var_os("CURSED_TIMER_DATA") {
+            return Some(PathBuf::from(dir));
+        }
+
+        #[cfg(any(target_os = "macos", target_os = "linux"))]
+        {
+            if let Some(dir) = xdg_dir("XDG_DATA_HOME", ".local/share") {
+                return Some(dir.join("cursed-timer"));
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            if let Some(data_local_dir) = dirs::data_local_dir() {
+                return Some(data_local_dir.join("CursedTimer"));
+            }
+        }
+
+        None
+    }
+
+    /// Returns the directory older versions kept every file in.
+    /// @return Option<PathBuf>: the old directory
+    fn legacy_dir() -> Option<PathBuf> {
         #[cfg(any(target_os = "macos", target_os = "linux"))]
         {
             if let Some(home_dir) = dirs::home_dir() {
-                let config_path = home_dir.join(".config/cursed-timer/config.toml");
-                return Some(config_path.to_string_lossy().to_string());
+                return Some(home_dir.join(".config/cursed-timer"));
             }
         }
 
         #[cfg(target_os = "windows")]
         {
             if let Some(data_local_dir) = dirs::data_local_dir() {
-                let config_path = data_local_dir.join("CursedTimer/config.toml");
-                return Some(config_path.to_string_lossy().to_string());
+                return Some(data_local_dir.join("CursedTimer"));
             }
         }
 
         None
     }
-    /// Loads the config file inside the config directory.
+
+    /// Uses the config, history and library of the profile `name` from now
+    /// on, each named profile having its own directories in `profiles`
+    pub fn set_profile(name: &str) {
+        *PROFILE.write().unwrap() = Some(name)
+            .filter(|&name| name != DEFAULT_PROFILE)
+            .map(String::from);
+    }
+
+    /// Returns the name of the profile in use.
+    /// @return String: the profile name
+    pub fn profile() -> String {
+        PROFILE
+            .read()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| String::from(DEFAULT_PROFILE))
+    }
+
+    /// Checks that `name` can be used as a profile directory
+    pub fn check_profile(name: &str) -> Result<(), String> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == ' ');
+        match valid {
+            true => Ok(()),
+            false => Err(format!(
+                "'{}' isn't a valid profile name, use letters, digits, spaces, - and _",
+                name
+            )),
+        }
+    }
+
+    /// Returns the default profile and every profile with a config, sorted.
+    /// @return Vec<String>: the profile names
+    pub fn profiles() -> Vec<String> {
+        let mut profiles: Vec<String> = CubeConfig::config_dir()
+            .and_then(|dir| fs::read_dir(dir.join("profiles")).ok())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name != DEFAULT_PROFILE)
+            .collect();
+        profiles.sort();
+        profiles.insert(0, String::from(DEFAULT_PROFILE));
+        profiles
+    }
+
+    /// Returns the directory of the profile in use inside `dir`.
+    /// @return PathBuf: the profile directory
+    fn profile_dir(dir: PathBuf) -> PathBuf {
+        match PROFILE.read().unwrap().as_ref() {
+            Some(name) => dir.join("profiles").join(name),
+            None => dir,
+        }
+    }
+
+    /// Returns the path of the history in the data directory.
+    /// @return Result<String, ConfigError>: the history path, or NoConfigDir
+    pub fn get_history_path() -> Result<String, ConfigError> {
+        let data_dir = CubeConfig::data_dir().ok_or(ConfigError::NoConfigDir)?;
+        let path = CubeConfig::profile_dir(data_dir).join("history.csv");
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// Returns the path of the algorithm library next to the history.
+    /// @return Result<String, ConfigError>: the library path, or NoConfigDir
+    pub fn get_library_path() -> Result<String, ConfigError> {
+        let data_dir = CubeConfig::data_dir().ok_or(ConfigError::NoConfigDir)?;
+        let path = CubeConfig::profile_dir(data_dir).join("algs.csv");
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// Returns the path of the config file in the config directory.
+    /// @return Option<String>: the config path as a string, or None if not found
+    pub fn get_config_path() -> Option<String> {
+        let path = CubeConfig::profile_dir(CubeConfig::config_dir()?).join("config.toml");
+        Some(path.to_string_lossy().to_string())
+    }
+
+    /// Moves the files older versions kept in `legacy_dir` to the config and
+    /// data directories, once, and creates the data directory of the profile
+    pub fn migrate() -> Result<(), ConfigError> {
+        let data_dir = CubeConfig::data_dir().ok_or(ConfigError::NoConfigDir)?;
+        let config_dir = CubeConfig::config_dir().ok_or(ConfigError::NoConfigDir)?;
+        let io_error = |path: &Path| {
+            let path = path.to_string_lossy().to_string();
+            move |err| ConfigError::Io { path, err }
+        };
+        let profile_dir = CubeConfig::profile_dir(data_dir.clone());
+        fs::create_dir_all(&profile_dir).map_err(io_error(&profile_dir))?;
+
+        let legacy_dir = match CubeConfig::legacy_dir() {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        let files = [
+            (&config_dir, "config.toml"),
+            (&data_dir, "history.csv"),
+            (&data_dir, "algs.csv"),
+            (&data_dir, "multibld-scrambles.txt"),
+        ];
+        for (dir, name) in files {
+            let (from, to) = (legacy_dir.join(name), dir.join(name));
+            if from == to || !from.exists() || to.exists() {
+                continue;
+            }
+            fs::create_dir_all(dir).map_err(io_error(dir))?;
+            // renaming fails across file systems
+            if fs::rename(&from, &to).is_err() {
+                fs::copy(&from, &to).map_err(io_error(&to))?;
+                fs::remove_file(&from).map_err(io_error(&from))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// When the config file was last written, to reload it once it changes
+    pub fn modified() -> Option<SystemTime> {
+        let path = CubeConfig::get_config_path()?;
+        fs::metadata(path).and_then(|meta| meta.modified()).ok()
+    }
+
+    /// Loads the config file inside the config directory, writing the
+    /// default one first if there is none. Keys missing from the file keep
+    /// their value of DEFAULT_CONFIG.
     /// Windows: %APPDATA%/CursedTimer/config.toml
-    /// Mac/Linux: $HOME/.config/cursed-timer/config.toml
+    /// Mac/Linux: $XDG_CONFIG_HOME/cursed-timer/config.toml, $HOME/.config by default
     /// @return: Config
-    pub fn new() -> Option<CubeConfig> {
-        let path = CubeConfig::get_config_path()?;
+    pub fn new() -> Result<CubeConfig, ConfigError> {
+        let path = CubeConfig::get_config_path().ok_or(ConfigError::NoConfigDir)?;
+        let io_error = |err| ConfigError::Io {
+            path: path.clone(),
+            err,
+        };
 
-        if File::open(&path).is_err() {
-            let mut file = File::create(path.clone()).unwrap();
-            let _ = file.write(DEFAULT_CONFIG.as_bytes());
+        if !Path::new(&path).exists() {
+            if let Some(dir) = Path::new(&path).parent() {
+                fs::create_dir_all(dir).map_err(io_error)?;
+            }
+            fs::write(&path, DEFAULT_CONFIG).map_err(io_error)?;
         }
+        let file = fs::read_to_string(&path).map_err(io_error)?;
+        CubeConfig::parse(&path, &file)
+    }
 
+    /// Reads the config `file` found at `path`, e.g. to check changes to it
+    /// before writing them
+    pub fn parse(path: &str, file: &str) -> Result<CubeConfig, ConfigError> {
         Config::builder()
-            .add_source(ConfigFile::with_name(&path))
+            .add_source(ConfigFile::from_str(DEFAULT_CONFIG, FileFormat::Toml))
+            .add_source(ConfigFile::from_str(file, FileFormat::Toml))
             .build()
-            .ok()?
-            .try_deserialize::<CubeConfig>()
-            .ok()
+            .and_then(|config| config.try_deserialize::<CubeConfig>())
+            .map(|mut config| {
+                config.scramble.min_moves = config.scramble.min_moves.min(MAX_MIN_MOVES);
+                config
+            })
+            .map_err(|err| ConfigError::invalid(path, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_moves_is_capped() {
+        let config = CubeConfig::parse("config.toml", DEFAULT_CONFIG).unwrap();
+        assert_eq!(config.scramble.min_moves, 2);
+        let file = DEFAULT_CONFIG.replace("min_moves = 2", "min_moves = 9");
+        let config = CubeConfig::parse("config.toml", &file).unwrap();
+        assert_eq!(config.scramble.min_moves, MAX_MIN_MOVES);
     }
 }
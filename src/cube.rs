@@ -0,0 +1,253 @@
+use std::sync::OnceLock;
+
+use crate::scramble::{Direction, Layers, Move, Scramble};
+
+pub type Vec3 = [i8; 3];
+type Permutation = [u8; 54];
+
+/// Position and outward normal of the sticker at `index`.
+/// Faces are ordered U R F D L B, each face's stickers row by row as seen from
+/// outside the cube, with x pointing right, y up and z to the front.
+fn sticker(index: usize) -> (Vec3, Vec3) {
+    let (r, c) = ((index % 9 / 3) as i8, (index % 3) as i8);
+    match index / 9 {
+        0 => ([c - 1, 1, r - 1], [0, 1, 0]),
+        1 => ([1, 1 - r, 1 - c], [1, 0, 0]),
+        2 => ([c - 1, 1 - r, 1], [0, 0, 1]),
+        3 => ([c - 1, -1, 1 - r], [0, -1, 0]),
+        4 => ([-1, 1 - r, c - 1], [-1, 0, 0]),
+        _ => ([1 - c, 1 - r, -1], [0, 0, -1]),
+    }
+}
+
+/// Rotates `v` a quarter turn counter clockwise around `axis` (right hand rule)
+fn rotate([x, y, z]: Vec3, axis: usize) -> Vec3 {
+    match axis {
+        0 => [x, -z, y],
+        1 => [z, y, -x],
+        _ => [-y, x, z],
+    }
+}
+
+/// Sticker permutation of turning the given layers (-1, 0, 1 along `axis`)
+/// `quarters` times counter clockwise around the positive `axis`
+fn turn(axis: usize, layers: &[i8], quarters: usize) -> Permutation {
+    let stickers: Vec<(Vec3, Vec3)> = (0..54).map(sticker).collect();
+    let mut perm = [0; 54];
+    for (i, &(mut pos, mut normal)) in stickers.iter().enumerate() {
+        if layers.contains(&pos[axis]) {
+            for _ in 0..quarters {
+                pos = rotate(pos, axis);
+                normal = rotate(normal, axis);
+            }
+        }
+        perm[i] = stickers.iter().position(|&s| s == (pos, normal)).unwrap() as u8;
+    }
+    perm
+}
+
+/// Layers turned, counted inwards from the face: the face only, the face and
+/// the middle layer, the middle layer only and the whole cube
+const DEPTHS: [&[i8]; 4] = [&[1], &[1, 0], &[0], &[1, 0, -1]];
+
+fn depth(layers: Layers) -> usize {
+    match layers {
+        Layers::Outer => 0,
+        Layers::Wide(2) => 1,
+        // a 3x3 only has 3 layers to turn
+        Layers::Wide(_) | Layers::Rotation => 3,
+        Layers::Slice => 2,
+    }
+}
+
+/// Clockwise quarter turns of each face indexed by `Direction`, for each of
+/// the `DEPTHS`
+fn face_turns() -> &'static [[Permutation; 4]; 6] {
+    static TURNS: OnceLock<[[Permutation; 4]; 6]> = OnceLock::new();
+    TURNS.get_or_init(|| {
+        std::array::from_fn(|face| {
+            std::array::from_fn(|d| {
+                let layers = |sign: i8| DEPTHS[d].iter().map(|l| l * sign).collect::<Vec<i8>>();
+                face_turn(Direction::ALL[face], &layers)
+            })
+        })
+    })
+}
+
+fn face_turn(direction: Direction, layers: &dyn Fn(i8) -> Vec<i8>) -> Permutation {
+    use Direction::*;
+    match direction {
+        Front => turn(2, &layers(1), 3),
+        Back => turn(2, &layers(-1), 1),
+        Down => turn(1, &layers(-1), 1),
+        Up => turn(1, &layers(1), 3),
+        Right => turn(0, &layers(1), 3),
+        Left => turn(0, &layers(-1), 1),
+    }
+}
+
+/// Stickers of each edge and corner piece, grouped by position
+pub fn pieces() -> &'static Vec<(Vec3, Vec<usize>)> {
+    static PIECES: OnceLock<Vec<(Vec3, Vec<usize>)>> = OnceLock::new();
+    PIECES.get_or_init(|| {
+        let mut pieces: Vec<(Vec3, Vec<usize>)> = vec![];
+        for i in 0..54 {
+            let (pos, _) = sticker(i);
+            if pos.iter().filter(|&&c| c != 0).count() < 2 {
+                continue;
+            }
+            match pieces.iter_mut().find(|(p, _)| *p == pos) {
+                Some((_, stickers)) => stickers.push(i),
+                None => pieces.push((pos, vec![i])),
+            }
+        }
+        pieces
+    })
+}
+
+/// Where `m` takes the sticker at each index, e.g. to follow pieces
+pub fn sticker_moves(m: &Move) -> [u8; 54] {
+    let mut cube = Cube(std::array::from_fn(|i| i as u8));
+    cube.apply(m);
+    let mut moves = [0; 54];
+    for (to, &from) in cube.0.iter().enumerate() {
+        moves[from as usize] = to as u8;
+    }
+    moves
+}
+
+fn dot(a: Vec3, b: Vec3) -> i8 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+/// Sticker level model of a 3x3x3, each sticker holds the index of the face
+/// it belongs to when solved.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cube([u8; 54]);
+
+impl Default for Cube {
+    fn default() -> Self {
+        Cube(std::array::from_fn(|i| (i / 9) as u8))
+    }
+}
+
+impl Cube {
+    pub fn scrambled(scramble: &Scramble) -> Cube {
+        let mut cube = Cube::default();
+        cube.apply_all(scramble.moves());
+        cube
+    }
+
+    fn permute(&mut self, perm: &Permutation) {
+        let old = self.0;
+        for (i, &to) in perm.iter().enumerate() {
+            self.0[to as usize] = old[i];
+        }
+    }
+
+    pub fn apply(&mut self, m: &Move) {
+        let perm = &face_turns()[m.direction as usize][depth(m.layers)];
+        for _ in 0..m.modifier.quarters() {
+            self.permute(perm);
+        }
+    }
+
+    pub fn apply_all(&mut self, moves: &[Move]) {
+        for m in moves {
+            self.apply(m);
+        }
+    }
+
+    /// Copy of the cube with each sticker replaced by `f(index, face)`, e.g.
+    /// to compare only some of the pieces
+    pub fn map_stickers(&self, f: impl Fn(usize, u8) -> u8) -> Cube {
+        Cube(std::array::from_fn(|i| f(i, self.0[i])))
+    }
+
+    /// All stickers of the piece at `pos` match their centers
+    fn piece_solved(&self, pos: Vec3) -> bool {
+        pieces()
+            .iter()
+            .filter(|(p, _)| *p == pos)
+            .flat_map(|(_, stickers)| stickers)
+            .all(|&i| self.0[i] == self.0[i / 9 * 9 + 4])
+    }
+
+    /// Face each sticker belongs on going by the centers, to find the pieces
+    /// whichever way the cube is held
+    pub fn facelets(&self) -> [u8; 54] {
+        std::array::from_fn(|i| {
+            (0..6)
+                .find(|&face| self.0[face * 9 + 4] == self.0[i])
+                .unwrap_or(0) as u8
+        })
+    }
+
+    /// Every piece solved at a position `in_block` is solved
+    pub fn pieces_solved(&self, in_block: impl Fn(Vec3) -> bool) -> bool {
+        pieces()
+            .iter()
+            .filter(|(pos, _)| in_block(*pos))
+            .all(|(pos, _)| self.piece_solved(*pos))
+    }
+
+    /// Every edge is oriented for the front / back axis: none of them needs
+    /// an F or B quarter turn to be solved
+    pub fn edges_oriented(&self) -> bool {
+        let center = |face: usize| self.0[face * 9 + 4];
+        let is_color = |sticker: usize, faces: [usize; 2]| {
+            faces.iter().any(|&face| self.0[sticker] == center(face))
+        };
+        pieces()
+            .iter()
+            .filter(|(_, stickers)| stickers.len() == 2)
+            .all(|(_, stickers)| {
+                // the sticker on U / D, or on F / B for an edge of the middle layer
+                let on =
+                    |faces: [usize; 2]| stickers.iter().position(|&i| faces.contains(&(i / 9)));
+                let key = on([0, 3]).or_else(|| on([2, 5])).unwrap_or(0);
+                let (key, other) = (stickers[key], stickers[1 - key]);
+                let flipped =
+                    is_color(key, [4, 1]) || (is_color(key, [2, 5]) && is_color(other, [0, 3]));
+                !flipped
+            })
+    }
+
+    /// Edges around `face` (0..6 in U R F D L B order) are solved
+    pub fn cross_solved(&self, face: usize) -> bool {
+        let (_, normal) = sticker(face * 9 + 4);
+        pieces()
+            .iter()
+            .filter(|(pos, stickers)| stickers.len() == 2 && dot(*pos, normal) == 1)
+            .all(|(pos, _)| self.piece_solved(*pos))
+    }
+
+    /// Number of solved corner / edge pairs in the first two layers, with
+    /// the first layer on `face`
+    pub fn f2l_pairs(&self, face: usize) -> usize {
+        let (_, normal) = sticker(face * 9 + 4);
+        pieces()
+            .iter()
+            .filter(|(pos, stickers)| stickers.len() == 3 && dot(*pos, normal) == 1)
+            .filter(|(corner, _)| {
+                let mut edge = *corner;
+                let axis = normal.iter().position(|&c| c != 0).unwrap();
+                edge[axis] = 0;
+                self.piece_solved(*corner) && self.piece_solved(edge)
+            })
+            .count()
+    }
+
+    /// All stickers of `face` match its center
+    pub fn face_solved(&self, face: usize) -> bool {
+        let stickers = &self.0[face * 9..face * 9 + 9];
+        stickers.iter().all(|&c| c == stickers[4])
+    }
+
+    /// Every face shows a single color, regardless of the cube's orientation
+    pub fn is_solved(&self) -> bool {
+        self.0
+            .chunks(9)
+            .all(|face| face.iter().all(|&c| c == face[4]))
+    }
+}
@@ -1,27 +1,93 @@
+use std::collections::HashMap;
 use std::fmt;
-use std::num;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
 use std::ops::Add;
+use std::path::Path;
 use std::str;
 use std::time::Duration;
 
-use chrono;
 use chrono::prelude::*;
-use csv;
+use csv::{ByteRecord, StringRecord};
 
-use crate::{scramble::Scramble, timer::Timer};
+use crate::{config::Mode, reconstruction::Reconstruction, scramble::Scramble, timer::Timer};
 
 #[derive(Clone)]
 pub struct SolveTime(pub Duration);
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseSolveTimeError {
+    Empty,
+    InvalidNumber(String),
+    OutOfRange(String),
+    InvalidFraction(String),
+    Unrecognized(String),
+}
+
+impl fmt::Display for ParseSolveTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseSolveTimeError::Empty => write!(f, "Time is empty"),
+            ParseSolveTimeError::InvalidNumber(s) => write!(f, "'{}' is not a valid number", s),
+            ParseSolveTimeError::OutOfRange(s) => {
+                write!(f, "'{}' has minutes or seconds greater than 59", s)
+            }
+            ParseSolveTimeError::InvalidFraction(s) => {
+                write!(f, "'{}' must have at most 3 decimal places", s)
+            }
+            ParseSolveTimeError::Unrecognized(s) => write!(
+                f,
+                "'{}' is not a valid result, expected e.g. 12.34, DNF(15.20) or +2 13.01",
+                s
+            ),
+        }
+    }
+}
+
+fn parse_digits(s: &str) -> Result<u64, ParseSolveTimeError> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseSolveTimeError::InvalidNumber(s.to_string()));
+    }
+    s.parse()
+        .map_err(|_| ParseSolveTimeError::InvalidNumber(s.to_string()))
+}
+
 impl str::FromStr for SolveTime {
-    type Err = num::ParseIntError;
-    // fmt: mm:ss.lll  (l = millisecond)
+    type Err = ParseSolveTimeError;
+    // fmt: [[h:]mm:]ss[.fff], e.g. 12.34, 1:02.55 or 00:13.010
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let minute: u64 = s[..2].parse()?;
-        let second: u64 = s[3..5].parse()?;
-        let millis: u64 = s[6..].parse()?;
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseSolveTimeError::Empty);
+        }
+
+        let (whole, fraction) = match s.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (s, ""),
+        };
+        let millis = if fraction.is_empty() {
+            0
+        } else if fraction.len() > 3 {
+            return Err(ParseSolveTimeError::InvalidFraction(s.to_string()));
+        } else {
+            parse_digits(fraction)? * 10_u64.pow(3 - fraction.len() as u32)
+        };
+
+        let mut parts = if whole.is_empty() { "0" } else { whole }.rsplit(':');
+        let mut total = parse_digits(parts.next().unwrap_or_default())?;
+        for (i, part) in parts.enumerate() {
+            let unit = 60_u64.pow(i as u32 + 1);
+            if i > 1 {
+                return Err(ParseSolveTimeError::InvalidNumber(s.to_string()));
+            }
+            if total >= unit {
+                return Err(ParseSolveTimeError::OutOfRange(s.to_string()));
+            }
+            total += parse_digits(part)? * unit;
+        }
+
         Ok(SolveTime(
-            Duration::from_secs(minute * 60_u64 + second) + Duration::from_millis(millis),
+            Duration::from_secs(total) + Duration::from_millis(millis),
         ))
     }
 }
@@ -38,6 +104,7 @@ impl fmt::Display for SolveTime {
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Penalty {
     No,
@@ -65,73 +132,492 @@ impl fmt::Display for Penalty {
     }
 }
 
+/// Result of a multi-blind attempt, e.g. 7/8
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct MultiBld {
+    pub solved: usize,
+    pub attempted: usize,
+}
+
+impl MultiBld {
+    /// Solved minus unsolved cubes
+    pub fn points(&self) -> i64 {
+        self.solved as i64 - (self.attempted - self.solved) as i64
+    }
+
+    /// Less than zero points, or a single solved cube, is a DNF
+    pub fn is_dnf(&self) -> bool {
+        self.points() < 0 || self.solved < 2
+    }
+
+    /// 10 minutes per cube up to an hour
+    pub fn time_limit(attempted: usize) -> Duration {
+        Duration::from_secs(60 * 10 * attempted.min(6) as u64)
+    }
+}
+
+impl fmt::Display for MultiBld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.solved, self.attempted)
+    }
+}
+
+impl str::FromStr for MultiBld {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (solved, attempted) = s.split_once('/').ok_or("Multi-blind result isn't valid")?;
+        let solved = solved.parse().map_err(|_| "Solved cubes isn't valid")?;
+        let attempted = attempted
+            .parse()
+            .map_err(|_| "Attempted cubes isn't valid")?;
+        if solved > attempted {
+            return Err("Solved cubes can't exceed attempted cubes");
+        }
+        Ok(MultiBld { solved, attempted })
+    }
+}
+
+/// A result typed in by hand, e.g. `12.34`, `1:02.55`, `DNF(15.20)` or
+/// `+2 13.01`. Like `History::penalize`, the +2 is added to the stored time.
+pub struct ManualEntry {
+    pub time: SolveTime,
+    pub penalty: Penalty,
+}
+
+impl str::FromStr for ManualEntry {
+    type Err = ParseSolveTimeError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let upper = s.to_ascii_uppercase();
+        for (prefix, penalty) in [("DNF", Penalty::DNF), ("DNS", Penalty::DNS)] {
+            if let Some(rest) = upper.strip_prefix(prefix) {
+                let rest = rest.trim();
+                let time = if rest.is_empty() {
+                    SolveTime(Duration::from_secs(0))
+                } else {
+                    rest.strip_prefix('(')
+                        .and_then(|r| r.strip_suffix(')'))
+                        .ok_or_else(|| ParseSolveTimeError::Unrecognized(s.to_string()))?
+                        .parse()?
+                };
+                return Ok(ManualEntry { time, penalty });
+            }
+        }
+        // +25.00 could be a +2 on 5.00 as well as 25.00
+        let plus_two = s
+            .strip_prefix("+2")
+            .filter(|rest| rest.starts_with(char::is_whitespace));
+        if let Some(rest) = plus_two.or_else(|| s.strip_suffix('+')) {
+            let SolveTime(d) = rest.parse()?;
+            return Ok(ManualEntry {
+                time: SolveTime(d + Duration::from_secs(2)),
+                penalty: Penalty::Time,
+            });
+        }
+        if s.starts_with('+') {
+            return Err(ParseSolveTimeError::Unrecognized(s.to_string()));
+        }
+        Ok(ManualEntry {
+            time: s.parse()?,
+            penalty: Penalty::No,
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct Entry {
     pub time: SolveTime,
-    scramble: Scramble,
-    date: chrono::DateTime<Utc>,
-    penalty: Penalty,
+    pub scramble: Scramble,
+    pub date: chrono::DateTime<Utc>,
+    pub penalty: Penalty,
+    pub moves: Option<Reconstruction>,
+    /// Duration of each phase of a multi-phase solve
+    pub splits: Vec<Duration>,
+    /// Every scramble of a multi puzzle attempt, `scramble` being the first
+    pub scrambles: Vec<Scramble>,
+    pub multi: Option<MultiBld>,
+    /// Session mode the entry was recorded in
+    pub mode: Mode,
+    /// Competition simulation round the solve belongs to
+    pub round: Option<usize>,
+    /// Algorithm trainer case that was set up, e.g. PLL Ua
+    pub case: Option<String>,
+    /// Moves of the optimal solution of a planned step, to compare with the
+    /// planned one in `moves`
+    pub optimal: Option<usize>,
+}
+
+impl Entry {
+    /// Whether a single 3x3 was scrambled, relays and multi-blind attempts
+    /// scramble several puzzles
+    pub fn is_single_3x3(&self) -> bool {
+        self.scrambles.len() <= 1 && !matches!(self.mode, Mode::Relay | Mode::MultiBld)
+    }
+}
+
+/// What loading the history file skipped
+#[derive(Default)]
+pub struct LoadReport {
+    /// Line number of each skipped line and why it was skipped
+    pub skipped: Vec<(u64, String)>,
+    /// File the skipped lines are kept in, as they would be lost on the next
+    /// save
+    pub quarantine: String,
+    /// Why the skipped lines couldn't be kept
+    pub error: Option<String>,
+    /// Version of a file written by a newer release, it is loaded read-only
+    /// so that nothing this one can't read is lost
+    pub newer: Option<u32>,
+}
+
+impl LoadReport {
+    /// Whether there is nothing to report
+    pub fn is_empty(&self) -> bool {
+        self.skipped.is_empty() && self.newer.is_none()
+    }
+}
+
+/// Version of the history format written by `save_csv`
+const VERSION: u32 = 1;
+
+/// First line of a versioned history file, up to the version
+const VERSION_PREFIX: &str = "# cursed-timer history v";
+
+/// Columns of the current version, in the order they are written
+const COLUMNS: [&str; 12] = [
+    "time",
+    "scramble",
+    "date",
+    "penalty",
+    "moves",
+    "splits",
+    "scrambles",
+    "multi",
+    "mode",
+    "round",
+    "case",
+    "optimal",
+];
+
+/// Values of a line of the history file by column, empty ones left out
+type Line = HashMap<String, String>;
+
+/// Upgrades of a line from each version of the format to the next one,
+/// `MIGRATIONS[v]` taking a line of version `v` to version `v + 1`. Columns
+/// are read by name, so a new column only needs one when its value can't be
+/// left out of older lines.
+const MIGRATIONS: [fn(&mut Line); VERSION as usize] = [infer_mode];
+
+/// Version 0 files weren't versioned, and had no mode for the entries from
+/// before modes were recorded
+fn infer_mode(line: &mut Line) {
+    if !line.contains_key("mode") {
+        let mode = match line.contains_key("multi") {
+            true => Mode::MultiBld,
+            false => Mode::Normal,
+        };
+        line.insert(String::from("mode"), mode.to_string());
+    }
+}
+
+/// Splits the version line off the history `file`, files from before the
+/// format was versioned being version 0
+fn split_version(file: &[u8]) -> (u32, &[u8]) {
+    if !file.starts_with(VERSION_PREFIX.as_bytes()) {
+        return (0, file);
+    }
+    let end = file
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(file.len(), |i| i + 1);
+    let version = str::from_utf8(&file[VERSION_PREFIX.len()..end])
+        .ok()
+        .and_then(|version| version.trim().parse().ok())
+        .unwrap_or(0);
+    (version, &file[end..])
+}
+
+fn required<'a>(line: &'a Line, name: &str) -> Result<&'a str, String> {
+    line.get(name)
+        .map(String::as_str)
+        .ok_or_else(|| format!("missing {}", name))
+}
+
+fn parse<T: str::FromStr>(value: &str, name: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} '{}'", name, value))
+}
+
+/// Parses the column `name` of `line` unless it is left out
+fn optional<T: str::FromStr>(line: &Line, name: &str) -> Result<Option<T>, String> {
+    line.get(name).map(|value| parse(value, name)).transpose()
+}
+
+fn parse_entry(line: &Line) -> Result<Entry, String> {
+    let list = |name: &str| line.get(name).map(String::as_str).unwrap_or_default();
+    Ok(Entry {
+        time: parse(required(line, "time")?, "time")?,
+        scramble: parse(required(line, "scramble")?, "scramble")?,
+        date: parse(required(line, "date")?, "date")?,
+        penalty: parse(required(line, "penalty")?, "penalty")?,
+        moves: optional(line, "moves")?,
+        splits: list("splits")
+            .split_whitespace()
+            .map(|split| parse::<SolveTime>(split, "split").map(|split| split.0))
+            .collect::<Result<_, _>>()?,
+        scrambles: list("scrambles")
+            .split(';')
+            .filter(|scramble| !scramble.is_empty())
+            .map(|scramble| parse(scramble, "scramble"))
+            .collect::<Result<_, _>>()?,
+        multi: optional(line, "multi")?,
+        mode: parse(required(line, "mode")?, "mode")?,
+        round: optional(line, "round")?,
+        case: line.get("case").cloned(),
+        optimal: optional(line, "optimal")?,
+    })
+}
+
+/// Appends the `rejected` lines to the file at `path`, under `headers` when
+/// the file is new
+fn quarantine(
+    path: &str,
+    headers: &ByteRecord,
+    rejected: &[ByteRecord],
+    unreadable: &[&[u8]],
+) -> csv::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let new = file.metadata()?.len() == 0;
+    let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(&file);
+    if new {
+        writer.write_byte_record(headers)?;
+    }
+    for record in rejected {
+        writer.write_byte_record(record)?;
+    }
+    writer.flush()?;
+    drop(writer);
+    // lines that aren't valid csv are kept as they were
+    for line in unreadable {
+        file.write_all(line)?;
+        if !line.ends_with(b"\n") {
+            file.write_all(b"\n")?;
+        }
+    }
+    Ok(())
+}
+
+/// Line of `rows` starting at `byte`, with its line break
+fn raw_line(rows: &[u8], byte: usize) -> &[u8] {
+    let rest = &rows[byte.min(rows.len())..];
+    match rest.iter().position(|&b| b == b'\n') {
+        Some(end) => &rest[..=end],
+        None => rest,
+    }
 }
 
 pub struct History {
     entries: Vec<Entry>,
     deleted: Vec<Entry>,
+    /// Version of the format the file was written in when newer than
+    /// `VERSION`, it is never saved then
+    read_only: Option<u32>,
 }
 
 const VEC_START_SIZE: usize = 200;
 
 impl History {
-    pub fn from_csv(file_path: &str) -> History {
+    /// Loads every valid line of the history file, moving the others to
+    /// the `quarantine` file of the report. Files of an older version of the
+    /// format are upgraded, keeping a copy of them as they were, files of a
+    /// newer one are left untouched and loaded read-only.
+    pub fn from_csv(file_path: &str) -> (History, LoadReport) {
         let mut history = History {
             entries: Vec::with_capacity(VEC_START_SIZE),
             deleted: Vec::new(),
+            read_only: None,
+        };
+        let mut report = LoadReport {
+            quarantine: Path::new(file_path)
+                .with_extension("rejected.csv")
+                .to_string_lossy()
+                .to_string(),
+            ..LoadReport::default()
+        };
+        let file = match fs::read(file_path) {
+            Ok(file) => file,
+            Err(_) => return (history, report),
         };
-        match csv::Reader::from_path(file_path) {
-            Ok(mut reader) => {
-                for result in reader.records() {
-                    if let Ok(record) = result {
-                        history.entries.push(Entry {
-                            time: record[0].parse::<SolveTime>().unwrap(),
-                            scramble: record[1].parse::<Scramble>().unwrap(),
-                            date: record[2].parse::<chrono::DateTime<Utc>>().unwrap(),
-                            penalty: record[3].parse::<Penalty>().unwrap(),
-                        })
+        let (version, rows) = split_version(&file);
+        if version > VERSION {
+            history.read_only = Some(version);
+            report.newer = Some(version);
+        }
+        let version_lines = (rows.len() < file.len()) as u64;
+        // older versions wrote fewer columns
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(rows);
+        let headers = reader.byte_headers().cloned().unwrap_or_default();
+        let columns = StringRecord::from_byte_record(headers.clone()).unwrap_or_default();
+        let mut rejected = vec![];
+        let mut unreadable = vec![];
+        for record in reader.byte_records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(err) => {
+                    match err.position() {
+                        Some(pos) => {
+                            report
+                                .skipped
+                                .push((pos.line() + version_lines, err.to_string()));
+                            unreadable.push(raw_line(rows, pos.byte() as usize));
+                        }
+                        // it can't be moved, so the file is left as it is
+                        None => {
+                            report.skipped.push((0, err.to_string()));
+                            report.error = Some(String::from("the line couldn't be found"));
+                        }
+                    }
+                    continue;
+                }
+            };
+            let line = record.position().map_or(0, |pos| pos.line());
+            let entry = StringRecord::from_byte_record(record.clone())
+                .map_err(|_| String::from("not valid UTF-8"))
+                .and_then(|record| {
+                    let mut line: Line = columns
+                        .iter()
+                        .zip(record.iter())
+                        .filter(|(_, value)| !value.is_empty())
+                        .map(|(column, value)| (column.to_string(), value.to_string()))
+                        .collect();
+                    for migrate in MIGRATIONS.iter().skip(version as usize) {
+                        migrate(&mut line);
                     }
+                    parse_entry(&line)
+                });
+            match entry {
+                Ok(entry) => history.entries.push(entry),
+                Err(reason) => {
+                    report.skipped.push((line + version_lines, reason));
+                    rejected.push(record);
                 }
             }
-            Err(_) => {}
-        };
-        history
+        }
+        if history.read_only.is_some() {
+            return (history, report);
+        }
+        let mut save = false;
+        if !rejected.is_empty() || !unreadable.is_empty() {
+            match quarantine(&report.quarantine, &headers, &rejected, &unreadable) {
+                // so that they are only moved once
+                Ok(()) => save = true,
+                Err(err) => report.error = Some(err.to_string()),
+            }
+        }
+        if version < VERSION {
+            let backup = Path::new(file_path).with_extension(format!("v{}.csv", version));
+            save |= fs::copy(file_path, backup).is_ok();
+        }
+        // lines that couldn't be kept stay in the file for now, as do the
+        // quarantined ones if it can't be written until the next save
+        if save && report.error.is_none() {
+            let _ = history.save_csv(file_path);
+        }
+        (history, report)
     }
 
-    pub fn save_csv(&self, file_path: &str) {
-        let mut writter = csv::Writer::from_path(file_path).unwrap();
-        writter
-            .write_record(&["time", "scramble", "date", "penalty"])
-            .unwrap();
+    pub fn save_csv(&self, file_path: &str) -> io::Result<()> {
+        if self.read_only.is_some() {
+            return Ok(());
+        }
+        let mut file = File::create(file_path)?;
+        writeln!(file, "{}{}", VERSION_PREFIX, VERSION)?;
+        let mut writter = csv::Writer::from_writer(file);
+        writter.write_record(COLUMNS)?;
         for entry in &self.entries {
-            writter
-                .write_record(&[
-                    entry.time.to_string(),
-                    entry.scramble.to_string(),
-                    entry.date.to_string(),
-                    entry.penalty.to_string(),
-                ])
-                .unwrap();
+            writter.write_record(&[
+                entry.time.to_string(),
+                entry.scramble.to_string(),
+                entry.date.to_string(),
+                entry.penalty.to_string(),
+                entry
+                    .moves
+                    .as_ref()
+                    .map(|moves| moves.to_string())
+                    .unwrap_or_default(),
+                entry
+                    .splits
+                    .iter()
+                    .map(|&split| SolveTime(split).to_string())
+                    .collect::<Vec<String>>()
+                    .join(" "),
+                entry
+                    .scrambles
+                    .iter()
+                    .map(|scramble| scramble.to_string())
+                    .collect::<Vec<String>>()
+                    .join(";"),
+                entry
+                    .multi
+                    .map(|multi| multi.to_string())
+                    .unwrap_or_default(),
+                entry.mode.to_string(),
+                entry
+                    .round
+                    .map(|round| round.to_string())
+                    .unwrap_or_default(),
+                entry.case.clone().unwrap_or_default(),
+                entry
+                    .optimal
+                    .map(|optimal| optimal.to_string())
+                    .unwrap_or_default(),
+            ])?;
         }
-        writter.flush().unwrap();
+        writter.flush()
     }
 
     pub fn summarize(&self) -> Vec<String> {
         self.entries
             .iter()
-            .map(|Entry { time, penalty, .. }| match penalty {
-                Penalty::No | Penalty::Time => time.to_string(),
-                _ => penalty.to_string(),
+            .map(|entry| match (entry.multi, &entry.penalty) {
+                (Some(multi), _) if multi.is_dnf() => format!("DNF ({})", multi),
+                (Some(multi), _) => {
+                    let secs = entry.time.0.as_secs();
+                    format!("{} {}:{:0>2}", multi, secs / 60, secs % 60)
+                }
+                (None, Penalty::No | Penalty::Time) if entry.mode == Mode::Fmc => {
+                    let moves = entry.moves.as_ref().map_or(0, |m| m.move_count());
+                    format!("{} moves", moves)
+                }
+                (None, Penalty::No | Penalty::Time) if entry.mode == Mode::Plan => {
+                    let moves = entry.moves.as_ref().map_or(0, |m| m.move_count());
+                    let optimal = entry.optimal.unwrap_or(moves);
+                    format!("{} moves (+{})", moves, moves.saturating_sub(optimal))
+                }
+                (None, Penalty::No | Penalty::Time) => entry.time.to_string(),
+                (None, penalty) => penalty.to_string(),
             })
             .collect()
     }
 
+    /// Version of the file when it was loaded read-only, see `from_csv`
+    pub fn read_only(&self) -> Option<u32> {
+        self.read_only
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Entry> {
+        self.entries.get_mut(index)
+    }
+
+    pub fn last_mut(&mut self) -> Option<&mut Entry> {
+        self.entries.last_mut()
+    }
+
     pub fn pop(&mut self) {
         if let Some(e) = self.entries.pop() {
             self.deleted.push(e);
@@ -144,15 +630,69 @@ impl History {
         }
     }
 
-    pub fn push(&mut self, timer: &Timer, scramble: &Scramble, penalty: Penalty) {
+    pub fn push(&mut self, timer: &Timer, scramble: &Scramble, penalty: Penalty, mode: Mode) {
+        self.push_time(SolveTime(timer.result), scramble, penalty, mode);
+        if let Some(entry) = self.entries.last_mut() {
+            entry.splits = timer.phases();
+        }
+    }
+
+    pub fn push_time(
+        &mut self,
+        time: SolveTime,
+        scramble: &Scramble,
+        penalty: Penalty,
+        mode: Mode,
+    ) {
         self.entries.push(Entry {
-            time: SolveTime(timer.result),
+            time,
             scramble: scramble.clone(),
             date: chrono::offset::Utc::now(),
             penalty,
+            moves: None,
+            splits: vec![],
+            scrambles: vec![],
+            multi: None,
+            mode,
+            round: None,
+            case: None,
+            optimal: None,
         });
     }
 
+    /// Solves of the competition simulation round `id`
+    pub fn round(&self, id: usize) -> Vec<&Entry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.round == Some(id))
+            .collect()
+    }
+
+    /// Solves of every competition simulation round, oldest round first
+    pub fn rounds(&self) -> Vec<Vec<&Entry>> {
+        let mut rounds: Vec<(usize, Vec<&Entry>)> = vec![];
+        for entry in &self.entries {
+            let id = match entry.round {
+                Some(id) => id,
+                None => continue,
+            };
+            match rounds.iter_mut().find(|(round, _)| *round == id) {
+                Some((_, solves)) => solves.push(entry),
+                None => rounds.push((id, vec![entry])),
+            }
+        }
+        rounds.into_iter().map(|(_, solves)| solves).collect()
+    }
+
+    /// Id for a round that hasn't been used yet
+    pub fn next_round(&self) -> usize {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.round)
+            .max()
+            .map_or(1, |round| round + 1)
+    }
+
     pub fn clear(&mut self) {
         self.entries.clear()
     }
@@ -198,3 +738,262 @@ impl History {
         (points, xbounds, ybounds)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manual(s: &str) -> Result<(Duration, Penalty), ParseSolveTimeError> {
+        s.parse::<ManualEntry>()
+            .map(|entry| (entry.time.0, entry.penalty))
+    }
+
+    /// Empty directory of its own for each test touching files
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cursed-timer-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn solve_time_formats() {
+        let time = |s: &str| s.parse::<SolveTime>().map(|time| time.0);
+        assert_eq!(time("12.34"), Ok(Duration::from_millis(12_340)));
+        assert_eq!(time("1:02.55"), Ok(Duration::from_millis(62_550)));
+        assert_eq!(time("00:13.010"), Ok(Duration::from_millis(13_010)));
+        assert_eq!(time("1:00:00"), Ok(Duration::from_secs(3600)));
+        assert_eq!(time(".5"), Ok(Duration::from_millis(500)));
+        assert_eq!(
+            SolveTime(Duration::from_millis(62_550)).to_string(),
+            "01:02.550"
+        );
+    }
+
+    #[test]
+    fn solve_time_errors() {
+        let time = |s: &str| s.parse::<SolveTime>().map(|time| time.0);
+        assert_eq!(time("  "), Err(ParseSolveTimeError::Empty));
+        assert_eq!(
+            time("1:75"),
+            Err(ParseSolveTimeError::OutOfRange(String::from("1:75")))
+        );
+        assert_eq!(
+            time("12.3456"),
+            Err(ParseSolveTimeError::InvalidFraction(String::from(
+                "12.3456"
+            )))
+        );
+        assert_eq!(
+            time("12.a"),
+            Err(ParseSolveTimeError::InvalidNumber(String::from("a")))
+        );
+        assert!(time("1:2:3:4").is_err());
+        assert!(time("-1").is_err());
+    }
+
+    #[test]
+    fn manual_entry_penalties() {
+        assert_eq!(
+            manual("DNF(15.20)"),
+            Ok((Duration::from_millis(15_200), Penalty::DNF))
+        );
+        assert_eq!(manual("dnf"), Ok((Duration::ZERO, Penalty::DNF)));
+        assert_eq!(manual("DNS"), Ok((Duration::ZERO, Penalty::DNS)));
+        // the +2 is part of the stored time
+        assert_eq!(
+            manual("+2 13.01"),
+            Ok((Duration::from_millis(15_010), Penalty::Time))
+        );
+        assert_eq!(
+            manual("+2\t13.01"),
+            Ok((Duration::from_millis(15_010), Penalty::Time))
+        );
+        assert_eq!(
+            manual("+25.00"),
+            Err(ParseSolveTimeError::Unrecognized(String::from("+25.00")))
+        );
+        assert_eq!(
+            manual("+2"),
+            Err(ParseSolveTimeError::Unrecognized(String::from("+2")))
+        );
+        assert_eq!(
+            manual("13.01+"),
+            Ok((Duration::from_millis(15_010), Penalty::Time))
+        );
+        assert_eq!(
+            manual(" 12.34 "),
+            Ok((Duration::from_millis(12_340), Penalty::No))
+        );
+        assert_eq!(
+            manual("DNF 15.20"),
+            Err(ParseSolveTimeError::Unrecognized(String::from("DNF 15.20")))
+        );
+        assert_eq!(
+            manual("+2 1:75"),
+            Err(ParseSolveTimeError::OutOfRange(String::from("1:75")))
+        );
+    }
+
+    #[test]
+    fn multi_bld_scoring() {
+        let multi = |s: &str| s.parse::<MultiBld>().unwrap();
+        assert_eq!(multi("7/8").points(), 6);
+        assert!(!multi("7/8").is_dnf());
+        assert_eq!(multi("2/4").points(), 0);
+        assert!(!multi("2/4").is_dnf());
+        assert_eq!(multi("1/2").points(), 0);
+        assert!(multi("1/2").is_dnf());
+        assert!(multi("2/5").is_dnf());
+        assert_eq!(multi("0/3").points(), -3);
+        assert!("3/2".parse::<MultiBld>().is_err());
+        assert!("3".parse::<MultiBld>().is_err());
+        assert_eq!(MultiBld::time_limit(2), Duration::from_secs(20 * 60));
+        assert_eq!(MultiBld::time_limit(10), Duration::from_secs(60 * 60));
+    }
+
+    #[test]
+    fn single_3x3_entries() {
+        let mut history = History {
+            entries: vec![],
+            deleted: vec![],
+            read_only: None,
+        };
+        let scramble = "R U".parse::<Scramble>().unwrap();
+        for mode in [Mode::Normal, Mode::Plan, Mode::Relay, Mode::MultiBld] {
+            history.push_time(SolveTime(Duration::ZERO), &scramble, Penalty::No, mode);
+        }
+        let single: Vec<bool> = history.entries().iter().map(Entry::is_single_3x3).collect();
+        assert_eq!(single, [true, true, false, false]);
+        let entry = history.get_mut(0).unwrap();
+        entry.scrambles = vec![scramble.clone(), scramble];
+        assert!(!entry.is_single_3x3());
+    }
+
+    #[test]
+    fn quarantines_unreadable_lines() {
+        let dir = temp_dir("quarantine");
+        let path = dir.join("history.csv");
+        let path = path.to_str().unwrap();
+        let date = Utc::now();
+        fs::write(
+            path,
+            format!(
+                "{VERSION_PREFIX}{VERSION}\n{}\n\
+                 00:12.340,R U,{date},No,,,,,normal,,,\n\
+                 00:10.000,R U,{date},Maybe,,,,,normal,,,\n\
+                 00:11.000,R Q,{date},No,,,,,normal,,,\n",
+                COLUMNS.join(","),
+            ),
+        )
+        .unwrap();
+
+        let (history, report) = History::from_csv(path);
+        assert_eq!(history.entries().len(), 1);
+        assert_eq!(
+            report.skipped,
+            [
+                (4, String::from("invalid penalty 'Maybe'")),
+                (5, String::from("invalid scramble 'R Q'")),
+            ]
+        );
+        assert_eq!(report.error, None);
+        let rejected = fs::read_to_string(&report.quarantine).unwrap();
+        assert!(rejected.starts_with("time,scramble,date,penalty"));
+        assert!(rejected.contains(",Maybe,") && rejected.contains(",R Q,"));
+        assert!(!fs::read_to_string(path).unwrap().contains(",Maybe,"));
+
+        // the lines are only moved once
+        let (history, report) = History::from_csv(path);
+        assert_eq!(history.entries().len(), 1);
+        assert!(report.is_empty());
+        assert_eq!(fs::read_to_string(&report.quarantine).unwrap(), rejected);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn raw_lines() {
+        let rows = b"a,b\nc,d\ne";
+        assert_eq!(raw_line(rows, 0), b"a,b\n");
+        assert_eq!(raw_line(rows, 4), b"c,d\n");
+        assert_eq!(raw_line(rows, 8), b"e");
+        assert_eq!(raw_line(rows, 20), b"");
+    }
+
+    #[test]
+    fn split_version_line() {
+        assert_eq!(
+            split_version(b"time,scramble\n"),
+            (0, &b"time,scramble\n"[..])
+        );
+        assert_eq!(
+            split_version(b"# cursed-timer history v1\ntime\n"),
+            (1, &b"time\n"[..])
+        );
+        assert_eq!(split_version(b"# cursed-timer history vx\n"), (0, &b""[..]));
+    }
+
+    #[test]
+    fn migrates_version_0() {
+        let dir = temp_dir("migrate");
+        let path = dir.join("history.csv");
+        let path = path.to_str().unwrap();
+        let date = Utc::now().to_string();
+        let file = format!(
+            "time,scramble,date,penalty,moves,splits,scrambles,multi\n\
+             00:12.340,R U,{date},No,,,,\n\
+             10:00.000,R U,{date},No,,,R U;F D,2/2\n",
+        );
+        fs::write(path, &file).unwrap();
+
+        let (history, report) = History::from_csv(path);
+        let modes: Vec<Mode> = history.entries().iter().map(|entry| entry.mode).collect();
+        assert_eq!(modes, [Mode::Normal, Mode::MultiBld]);
+        assert!(report.is_empty());
+        let backup = fs::read_to_string(dir.join("history.v0.csv")).unwrap();
+        assert_eq!(backup, file);
+        let saved = fs::read_to_string(path).unwrap();
+        assert!(saved.starts_with("# cursed-timer history v1\ntime,scramble,date,penalty"));
+
+        // the upgraded file loads as it is
+        let (history, report) = History::from_csv(path);
+        assert_eq!(history.entries().len(), 2);
+        assert!(report.is_empty());
+        assert_eq!(fs::read_to_string(path).unwrap(), saved);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn save_errors_are_returned() {
+        let dir = temp_dir("unwritable");
+        let (history, _) = History::from_csv(dir.join("history.csv").to_str().unwrap());
+        // a directory can't be written as a file
+        assert!(history.save_csv(dir.to_str().unwrap()).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn newer_version_is_read_only() {
+        let dir = temp_dir("newer");
+        let path = dir.join("history.csv");
+        let path = path.to_str().unwrap();
+        let file = format!(
+            "# cursed-timer history v{}\ntime,scramble,date,penalty,mode,future\n\
+             00:12.340,R U,{},No,normal,42\nbroken\n",
+            VERSION + 1,
+            Utc::now()
+        );
+        fs::write(path, &file).unwrap();
+
+        let (history, report) = History::from_csv(path);
+        assert_eq!(history.entries().len(), 1);
+        assert_eq!(report.newer, Some(VERSION + 1));
+        assert_eq!(history.read_only(), Some(VERSION + 1));
+        assert_eq!(report.skipped.len(), 1);
+        history.save_csv(path).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), file);
+        assert!(!Path::new(&report.quarantine).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
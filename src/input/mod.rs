@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::net::TcpStream;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+use crossterm::event::KeyEvent;
+
+use crate::scramble::Move;
+
+pub mod smartcube;
+pub mod stackmat;
+
+pub enum InputEvent {
+    Key(KeyEvent),
+    Stackmat(stackmat::Packet),
+    CubeMove(Move),
+}
+
+/// A device that feeds the app events alongside the keyboard, e.g. a
+/// Stackmat or a smart cube. Each source is read on its own thread.
+pub trait EventSource: Send {
+    /// Reads events until the source is exhausted or the receiver hangs up
+    fn run(&mut self, tx: &Sender<InputEvent>) -> io::Result<()>;
+}
+
+pub fn spawn(mut source: Box<dyn EventSource>, tx: Sender<InputEvent>) {
+    thread::spawn(move || {
+        let _res = source.run(&tx);
+        #[cfg(feature = "debug")]
+        if let Err(err) = _res {
+            tracing::error!("Input source failed: {}", err);
+        }
+    });
+}
+
+/// Checks a source can be read while the timer runs, stdin is where the
+/// keys are read from so only `stackmat-replay` may use it
+pub fn check_source(source: &str) -> Result<(), String> {
+    match source {
+        "-" => Err(String::from(
+            "The timer reads keys from stdin, use a device, file, pipe or tcp://host:port instead of -",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Opens a source as a byte stream:
+/// `-` for stdin (see `check_source`), `tcp://host:port` for a socket
+/// bridge, or a file / device / pipe path
+pub fn open(source: &str) -> io::Result<Box<dyn Read + Send>> {
+    if source == "-" {
+        Ok(Box::new(io::stdin()))
+    } else if let Some(addr) = source.strip_prefix("tcp://") {
+        Ok(Box::new(TcpStream::connect(addr)?))
+    } else {
+        Ok(Box::new(File::open(source)?))
+    }
+}
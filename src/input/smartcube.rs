@@ -0,0 +1,386 @@
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::mpsc::Sender;
+
+use serde_derive::Deserialize;
+
+use super::{open, EventSource, InputEvent};
+use crate::scramble::{Direction, Layers, Modifier, Move};
+
+/// Faces in the order GAN and Moyu cubes number them
+const URFDLB: [Direction; 6] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Front,
+    Direction::Down,
+    Direction::Left,
+    Direction::Back,
+];
+
+/// Cubes talk Bluetooth LE, which we leave to a bridge (e.g. a small script
+/// around a BLE library) that forwards the decrypted notifications as bytes.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// Moves in notation separated by whitespace, e.g. `R U R' U'`
+    #[default]
+    Text,
+    /// GAN gen 2+ 20 byte move notifications
+    Gan,
+    /// Giiker / Mi Smart 20 byte state notifications
+    Giiker,
+    /// MoYu (MHC / WeiLong AI) turn notifications
+    Moyu,
+}
+
+impl std::str::FromStr for Protocol {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Protocol::Text),
+            "gan" => Ok(Protocol::Gan),
+            "giiker" => Ok(Protocol::Giiker),
+            "moyu" => Ok(Protocol::Moyu),
+            _ => Err(format!(
+                "'{}' is not a smart cube protocol, expected text, gan, giiker or moyu",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SmartCubeConfig {
+    pub source: String,
+    #[serde(default)]
+    pub protocol: Protocol,
+}
+
+fn quarter(direction: Direction, clockwise: bool) -> Move {
+    Move {
+        direction,
+        modifier: if clockwise {
+            Modifier::No
+        } else {
+            Modifier::Prime
+        },
+        layers: Layers::Outer,
+    }
+}
+
+/// Reads `len` bits starting at bit `start`, most significant bit first
+fn bits(data: &[u8], start: usize, len: usize) -> u32 {
+    (start..start + len).fold(0, |acc, i| {
+        (acc << 1) | ((data[i / 8] >> (7 - i % 8)) & 1) as u32
+    })
+}
+
+#[derive(Default)]
+struct Gan {
+    move_count: Option<u32>,
+}
+
+impl Gan {
+    /// A move notification carries a rolling move counter and the last 7 moves,
+    /// newest first, so moves missed between notifications are recovered.
+    fn decode(&mut self, frame: &[u8; 20]) -> Vec<Move> {
+        if bits(frame, 0, 4) != 2 {
+            return vec![];
+        }
+        let count = bits(frame, 4, 8);
+        let new = match self.move_count.replace(count) {
+            Some(last) => (count.wrapping_sub(last) & 0xff).min(7) as usize,
+            None => 0,
+        };
+        (0..new)
+            .rev()
+            .map(|i| {
+                let m = bits(frame, 12 + 5 * i, 5) as usize;
+                quarter(URFDLB[(m >> 1) % 6], m & 1 == 0)
+            })
+            .collect()
+    }
+}
+
+/// The last move is in bytes 16 and 17, the rest of the frame is the cube state
+fn decode_giiker(frame: &[u8; 20]) -> Option<Move> {
+    use Direction::*;
+    let direction = match frame[16] {
+        1 => Back,
+        2 => Down,
+        3 => Left,
+        4 => Up,
+        5 => Right,
+        6 => Front,
+        _ => return None,
+    };
+    let modifier = match frame[17] {
+        1 => Modifier::No,
+        2 | 9 => Modifier::Twice,
+        3 => Modifier::Prime,
+        _ => return None,
+    };
+    Some(Move {
+        direction,
+        modifier,
+        layers: Layers::Outer,
+    })
+}
+
+#[derive(Default)]
+struct Moyu {
+    /// Rotation of each face in ninths of a turn
+    faces: [i32; 6],
+}
+
+impl Moyu {
+    /// Each turn reports a partial rotation of a face, a quarter turn is
+    /// complete once the face crosses the halfway mark of its ninths.
+    fn decode(&mut self, face: u8, rotation: i8) -> Option<Move> {
+        let face = face as usize;
+        let axis = *[3, 4, 5, 1, 2, 0].get(face)?;
+        let prev = self.faces[face];
+        let cur = prev + (rotation as f64 / 36.0).round() as i32;
+        self.faces[face] = (cur + 9) % 9;
+        if prev >= 5 && cur <= 4 {
+            Some(quarter(URFDLB[axis], false))
+        } else if prev <= 4 && cur >= 5 {
+            Some(quarter(URFDLB[axis], true))
+        } else {
+            None
+        }
+    }
+}
+
+pub struct SmartCube(pub SmartCubeConfig);
+
+impl SmartCube {
+    fn send_all(tx: &Sender<InputEvent>, moves: Vec<Move>) -> bool {
+        moves
+            .into_iter()
+            .all(|m| tx.send(InputEvent::CubeMove(m)).is_ok())
+    }
+}
+
+impl EventSource for SmartCube {
+    fn run(&mut self, tx: &Sender<InputEvent>) -> io::Result<()> {
+        let mut reader = BufReader::new(open(&self.0.source)?);
+        let mut gan = Gan::default();
+        let mut moyu = Moyu::default();
+        loop {
+            let moves = match self.0.protocol {
+                Protocol::Text => {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line)? == 0 {
+                        return Ok(());
+                    }
+                    line.split_whitespace()
+                        .filter_map(|m| m.parse().ok())
+                        .collect()
+                }
+                Protocol::Gan | Protocol::Giiker => {
+                    let mut frame = [0; 20];
+                    reader.read_exact(&mut frame)?;
+                    match self.0.protocol {
+                        Protocol::Gan => gan.decode(&frame),
+                        _ => decode_giiker(&frame).into_iter().collect(),
+                    }
+                }
+                Protocol::Moyu => {
+                    let mut count = [0; 1];
+                    reader.read_exact(&mut count)?;
+                    let mut turns = vec![0; 6 * count[0] as usize];
+                    reader.read_exact(&mut turns)?;
+                    // each turn is a 4 byte timestamp, the face and its rotation
+                    turns
+                        .chunks(6)
+                        .filter_map(|t| moyu.decode(t[4], t[5] as i8))
+                        .collect()
+                }
+            };
+            if !SmartCube::send_all(tx, moves) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(moves: &[Move]) -> String {
+        moves
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// GAN move notification with its counter and the last moves, newest
+    /// first, as 5 bit face * 2 + counter-clockwise codes
+    fn gan_frame(kind: u32, count: u8, moves: &[u32]) -> [u8; 20] {
+        let mut bits = vec![];
+        let mut push = |value: u32, len: usize| {
+            bits.extend((0..len).rev().map(|i| (value >> i) & 1 == 1));
+        };
+        push(kind, 4);
+        push(count as u32, 8);
+        for &m in moves {
+            push(m, 5);
+        }
+        let mut frame = [0; 20];
+        for (i, &bit) in bits.iter().enumerate() {
+            frame[i / 8] |= (bit as u8) << (7 - i % 8);
+        }
+        frame
+    }
+
+    fn giiker_frame(face: u8, turn: u8) -> [u8; 20] {
+        let mut frame = [0x12; 20];
+        frame[16] = face;
+        frame[17] = turn;
+        frame
+    }
+
+    #[test]
+    fn reads_bits_msb_first() {
+        assert_eq!(bits(&[0b1010_0000, 0xff], 0, 3), 0b101);
+        assert_eq!(bits(&[0b0000_0011, 0b1000_0000], 6, 3), 0b111);
+    }
+
+    #[test]
+    fn gan_moves_since_the_last_notification() {
+        let mut gan = Gan::default();
+        // the first notification only sets the counter
+        assert_eq!(names(&gan.decode(&gan_frame(2, 10, &[0]))), "");
+        assert_eq!(names(&gan.decode(&gan_frame(2, 11, &[2, 0]))), "R");
+        assert_eq!(names(&gan.decode(&gan_frame(2, 11, &[2, 0]))), "");
+        assert_eq!(names(&gan.decode(&gan_frame(2, 13, &[5, 10, 2]))), "B F'");
+    }
+
+    #[test]
+    fn gan_counter_wraps_around() {
+        let mut gan = Gan::default();
+        gan.decode(&gan_frame(2, 254, &[]));
+        assert_eq!(names(&gan.decode(&gan_frame(2, 1, &[7, 6, 1]))), "U' D D'");
+        // at most the 7 moves of a notification can be recovered
+        let moves = gan.decode(&gan_frame(2, 41, &[0, 2, 4, 6, 8, 10, 0]));
+        assert_eq!(names(&moves), "U B L D F R U");
+    }
+
+    #[test]
+    fn gan_ignores_other_notifications() {
+        let mut gan = Gan::default();
+        gan.decode(&gan_frame(2, 10, &[]));
+        assert_eq!(names(&gan.decode(&gan_frame(4, 200, &[2]))), "");
+        assert_eq!(names(&gan.decode(&gan_frame(2, 11, &[2]))), "R");
+    }
+
+    #[test]
+    fn giiker_last_move() {
+        let decode = |face, turn| decode_giiker(&giiker_frame(face, turn)).map(|m| m.to_string());
+        assert_eq!(decode(5, 1), Some(String::from("R")));
+        assert_eq!(decode(4, 3), Some(String::from("U'")));
+        // half turns come either as 2 or as 9
+        assert_eq!(decode(1, 2), Some(String::from("B2")));
+        assert_eq!(decode(6, 9), Some(String::from("F2")));
+        assert_eq!(decode(0, 1), None);
+        assert_eq!(decode(7, 1), None);
+        assert_eq!(decode(2, 4), None);
+    }
+
+    fn spin(moyu: &mut Moyu, face: u8, rotation: i8) -> Option<String> {
+        moyu.decode(face, rotation).map(|m| m.to_string())
+    }
+
+    #[test]
+    fn moyu_turns_at_the_halfway_mark() {
+        let mut moyu = Moyu::default();
+        // face 3 is R, a ninth of a quarter turn is reported as 36
+        let turns: Vec<Option<String>> = (0..9).map(|_| spin(&mut moyu, 3, 36)).collect();
+        assert_eq!(turns.iter().flatten().collect::<Vec<_>>(), ["R"]);
+        assert_eq!(turns[4], Some(String::from("R")));
+        assert_eq!(moyu.faces[3], 0);
+    }
+
+    #[test]
+    fn moyu_wraps_around() {
+        let mut moyu = Moyu::default();
+        // back past the aligned position, then on to the halfway mark
+        assert_eq!(spin(&mut moyu, 0, -72), None);
+        assert_eq!(moyu.faces[0], 7);
+        assert_eq!(spin(&mut moyu, 0, -72), None);
+        assert_eq!(spin(&mut moyu, 0, -36), Some(String::from("D'")));
+        // turns of up to 4 ninths are rounded to the nearest ninth
+        let mut moyu = Moyu::default();
+        assert_eq!(spin(&mut moyu, 5, 127), None);
+        assert_eq!(spin(&mut moyu, 5, 40), Some(String::from("U")));
+        assert_eq!(spin(&mut moyu, 5, 127), None);
+        assert_eq!(moyu.faces[5], 0);
+    }
+
+    #[test]
+    fn moyu_ignores_unknown_faces() {
+        let mut moyu = Moyu::default();
+        assert_eq!(spin(&mut moyu, 6, 127), None);
+        assert_eq!(spin(&mut moyu, 255, 127), None);
+        assert_eq!(spin(&mut moyu, 1, 10), None);
+        assert_eq!(moyu.faces, [0; 6]);
+    }
+
+    /// Moves sent for the bytes of `stream` and how reading it ended
+    fn run(protocol: Protocol, stream: &[u8]) -> (String, io::Result<()>) {
+        let path = std::env::temp_dir().join(format!(
+            "cursed-timer-smartcube-{:?}-{}",
+            protocol,
+            std::process::id()
+        ));
+        std::fs::write(&path, stream).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let result = SmartCube(SmartCubeConfig {
+            source: path.to_string_lossy().to_string(),
+            protocol,
+        })
+        .run(&tx);
+        std::fs::remove_file(&path).unwrap();
+        let moves: Vec<Move> = rx
+            .try_iter()
+            .map(|event| match event {
+                InputEvent::CubeMove(m) => m,
+                _ => panic!("not a cube move"),
+            })
+            .collect();
+        (names(&moves), result)
+    }
+
+    #[test]
+    fn runs_byte_streams() {
+        let (moves, result) = run(Protocol::Text, b"R U' Q2\nF2\n");
+        assert_eq!(moves, "R U' F2");
+        assert!(result.is_ok());
+
+        let mut stream = gan_frame(2, 0, &[]).to_vec();
+        stream.extend(gan_frame(2, 1, &[3]));
+        let (moves, result) = run(Protocol::Gan, &stream);
+        assert_eq!(moves, "R'");
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut stream = giiker_frame(3, 1).to_vec();
+        stream.extend(&giiker_frame(0, 0)[..12]);
+        let (moves, result) = run(Protocol::Giiker, &stream);
+        assert_eq!(moves, "L");
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        // two notifications of 3 and 2 turns, the last one cut short
+        let turn = |face: u8, rotation: i8| [0, 0, 0, 0, face, rotation as u8];
+        let mut stream = vec![3];
+        stream.extend(turn(3, 127));
+        stream.extend(turn(3, 36));
+        stream.extend(turn(4, -36));
+        stream.extend([2]);
+        stream.extend(turn(3, 127));
+        let (moves, result) = run(Protocol::Moyu, &stream);
+        assert_eq!(moves, "R");
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,465 @@
+use std::fmt;
+use std::io::{self, Read};
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use serde_derive::Deserialize;
+
+use super::{open, EventSource, InputEvent};
+
+/// Stackmat timers talk RS232 at 1200 baud, 8 data bits, no parity, 1 stop bit
+const BAUD_RATE: u32 = 1200;
+/// A quiet line for this many bit periods marks the idle (mark) level
+const IDLE_BITS: u32 = 12;
+/// Samples closer to zero than this keep the previous level
+const NOISE_FLOOR: i16 = 256;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Raw bytes, e.g. a serial device already configured for 1200 8N1
+    #[default]
+    Serial,
+    /// Signed 16-bit little endian mono PCM, e.g. from `arecord -f S16_LE`
+    Pcm,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct StackmatConfig {
+    pub source: String,
+    #[serde(default)]
+    pub format: Format,
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: u32,
+}
+
+fn default_sample_rate() -> u32 {
+    44100
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Timer was reset and shows 0:00.00
+    Idle,
+    /// Both hands were placed long enough, releasing starts the timer
+    Ready,
+    Running,
+    Stopped,
+    LeftHand,
+    RightHand,
+    BothHands,
+}
+
+impl Status {
+    fn from_byte(b: u8) -> Option<Status> {
+        match b {
+            b'I' => Some(Status::Idle),
+            b'A' => Some(Status::Ready),
+            b' ' => Some(Status::Running),
+            b'S' => Some(Status::Stopped),
+            b'L' => Some(Status::LeftHand),
+            b'R' => Some(Status::RightHand),
+            b'C' => Some(Status::BothHands),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    pub status: Status,
+    pub time: Duration,
+}
+
+impl Packet {
+    /// Parses a packet without its trailing `\n\r`.
+    /// Gen 2/3 timers send `status m s s h h checksum`, gen 4 timers add a
+    /// thousandths digit before the checksum.
+    fn parse(bytes: &[u8]) -> Option<Packet> {
+        let (status, rest) = bytes.split_first()?;
+        let (checksum, digits) = rest.split_last()?;
+        if digits.len() != 5 && digits.len() != 6 {
+            return None;
+        }
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        let digits: Vec<u64> = digits.iter().map(|d| (d - b'0') as u64).collect();
+        if *checksum as u64 != 64 + digits.iter().sum::<u64>() {
+            return None;
+        }
+
+        let seconds = digits[0] * 60 + digits[1] * 10 + digits[2];
+        let millis = digits[3] * 100 + digits[4] * 10 + digits.get(5).copied().unwrap_or(0);
+        Some(Packet {
+            status: Status::from_byte(*status)?,
+            time: Duration::from_secs(seconds) + Duration::from_millis(millis),
+        })
+    }
+}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let millis = self.time.as_millis();
+        write!(
+            f,
+            "{:?} {}:{:0>2}.{:0>3}",
+            self.status,
+            millis / 60_000,
+            millis / 1000 % 60,
+            millis % 1000
+        )
+    }
+}
+
+/// Splits a byte stream into packets terminated by `\n\r`.
+#[derive(Default)]
+pub struct ByteDecoder {
+    buffer: Vec<u8>,
+}
+
+impl ByteDecoder {
+    pub fn feed(&mut self, byte: u8) -> Option<Packet> {
+        self.buffer.push(byte);
+        if !self.buffer.ends_with(b"\n\r") {
+            if self.buffer.len() > 16 {
+                self.buffer.remove(0);
+            }
+            return None;
+        }
+        let len = self.buffer.len() - 2;
+        // the packet is the last 7 or 8 bytes before the terminator
+        let packet = [8, 7]
+            .iter()
+            .filter(|&&n| n <= len)
+            .find_map(|&n| Packet::parse(&self.buffer[len - n..len]));
+        self.buffer.clear();
+        packet
+    }
+}
+
+enum Uart {
+    Idle,
+    Receiving {
+        next_sample: f64,
+        bits: u32,
+        byte: u8,
+    },
+}
+
+/// Recovers the serial bytes from an audio recording of the Stackmat signal.
+/// The polarity is detected from the level the line rests at between packets.
+pub struct PcmDecoder {
+    samples_per_bit: f64,
+    position: u64,
+    level: bool,
+    run: u64,
+    idle_level: Option<bool>,
+    uart: Uart,
+    bytes: ByteDecoder,
+}
+
+impl PcmDecoder {
+    pub fn new(sample_rate: u32) -> Self {
+        PcmDecoder {
+            samples_per_bit: sample_rate as f64 / BAUD_RATE as f64,
+            position: 0,
+            level: false,
+            run: 0,
+            idle_level: None,
+            uart: Uart::Idle,
+            bytes: ByteDecoder::default(),
+        }
+    }
+
+    pub fn feed(&mut self, sample: i16) -> Option<Packet> {
+        self.position += 1;
+        if sample.unsigned_abs() >= NOISE_FLOOR as u16 && (sample > 0) != self.level {
+            self.level = sample > 0;
+            self.run = 0;
+        }
+        self.run += 1;
+        if self.run as f64 >= self.samples_per_bit * IDLE_BITS as f64 {
+            self.idle_level = Some(self.level);
+        }
+        let idle_level = self.idle_level?;
+
+        match self.uart {
+            Uart::Idle => {
+                if self.level != idle_level {
+                    // sample each bit in its middle, skipping the start bit
+                    self.uart = Uart::Receiving {
+                        next_sample: self.position as f64 + self.samples_per_bit * 1.5,
+                        bits: 0,
+                        byte: 0,
+                    };
+                }
+                None
+            }
+            Uart::Receiving {
+                ref mut next_sample,
+                ref mut bits,
+                ref mut byte,
+            } => {
+                if (self.position as f64) < *next_sample {
+                    return None;
+                }
+                *next_sample += self.samples_per_bit;
+                if *bits < 8 {
+                    if self.level == idle_level {
+                        *byte |= 1 << *bits;
+                    }
+                    *bits += 1;
+                    return None;
+                }
+                // stop bit, a framing error drops the byte
+                let byte = *byte;
+                let framed = self.level == idle_level;
+                self.uart = Uart::Idle;
+                if framed {
+                    self.bytes.feed(byte)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Decodes every packet in the source, calling `f` for each one until the
+/// source is exhausted or `f` returns false.
+pub fn decode<F: FnMut(Packet) -> bool>(config: &StackmatConfig, mut f: F) -> io::Result<()> {
+    let mut reader = io::BufReader::new(open(&config.source)?);
+    let mut buf = [0_u8; 4096];
+    let mut bytes = ByteDecoder::default();
+    let mut pcm = PcmDecoder::new(config.sample_rate);
+    let mut odd: Option<u8> = None;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        for &b in &buf[..n] {
+            let packet = match config.format {
+                Format::Serial => bytes.feed(b),
+                Format::Pcm => match odd.take() {
+                    Some(lo) => pcm.feed(i16::from_le_bytes([lo, b])),
+                    None => {
+                        odd = Some(b);
+                        None
+                    }
+                },
+            };
+            if let Some(packet) = packet {
+                if !f(packet) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Drives the timer from a Stackmat, sending a packet whenever the timer
+/// status or time changes.
+pub struct Stackmat(pub StackmatConfig);
+
+impl EventSource for Stackmat {
+    fn run(&mut self, tx: &Sender<InputEvent>) -> io::Result<()> {
+        let mut last: Option<Packet> = None;
+        decode(&self.0, |packet| {
+            if last == Some(packet) {
+                return true;
+            }
+            last = Some(packet);
+            tx.send(InputEvent::Stackmat(packet)).is_ok()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bytes of a packet with its checksum and terminator, `digits` being
+    /// 5 for gen 2/3 timers and 6 for gen 4 ones
+    fn packet(status: u8, digits: &str) -> Vec<u8> {
+        let sum: u32 = digits.bytes().map(|d| (d - b'0') as u32).sum();
+        let mut bytes = vec![status];
+        bytes.extend(digits.bytes());
+        bytes.push((64 + sum) as u8);
+        bytes.extend(b"\n\r");
+        bytes
+    }
+
+    /// Line levels of `bytes` sent at 1200 8N1 with some idle line around
+    /// them, idle being mark (true)
+    fn bits(bytes: &[u8]) -> Vec<bool> {
+        let mut bits = vec![true; 2 * IDLE_BITS as usize];
+        for &byte in bytes {
+            bits.push(false);
+            bits.extend((0..8).map(|i| byte & (1 << i) != 0));
+            bits.extend([true, true]);
+        }
+        bits.extend(vec![true; IDLE_BITS as usize]);
+        bits
+    }
+
+    /// Samples of `bits` at `sample_rate` with noise below the noise floor,
+    /// `inverted` recording the idle line as negative
+    fn samples(bits: &[bool], sample_rate: u32, inverted: bool) -> Vec<i16> {
+        let samples_per_bit = sample_rate as f64 / BAUD_RATE as f64;
+        let count = (bits.len() as f64 * samples_per_bit) as usize;
+        (0..count)
+            .map(|i| {
+                let high = bits[(i as f64 / samples_per_bit) as usize] != inverted;
+                let noise = [-120, 40, 200, -60][i % 4];
+                match (high, i % 97) {
+                    // a spike of the other level too short to be a bit
+                    (_, 0) => noise,
+                    (true, _) => 8000 + noise,
+                    (false, _) => -8000 + noise,
+                }
+            })
+            .collect()
+    }
+
+    fn feed_pcm(samples: &[i16], sample_rate: u32) -> Vec<Packet> {
+        let mut decoder = PcmDecoder::new(sample_rate);
+        samples.iter().filter_map(|&s| decoder.feed(s)).collect()
+    }
+
+    fn at(status: Status, millis: u64) -> Packet {
+        Packet {
+            status,
+            time: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn parses_packets() {
+        let parse = |bytes: &[u8]| Packet::parse(&bytes[..bytes.len() - 2]);
+        assert_eq!(
+            parse(&packet(b' ', "12345")),
+            Some(at(Status::Running, 83_450))
+        );
+        assert_eq!(
+            parse(&packet(b'S', "012345")),
+            Some(at(Status::Stopped, 12_345))
+        );
+        assert_eq!(parse(&packet(b'X', "00000")), None);
+        assert_eq!(parse(&packet(b'I', "0000")), None);
+        let mut bad = packet(b'S', "01234");
+        bad[6] += 1;
+        assert_eq!(parse(&bad), None);
+    }
+
+    #[test]
+    fn byte_decoder_skips_garbage_and_bad_checksums() {
+        let mut stream = b"\x00garbage".to_vec();
+        stream.extend(packet(b'I', "00000"));
+        let mut bad = packet(b'S', "01234");
+        bad[6] -= 1;
+        stream.extend(bad);
+        stream.extend(packet(b'S', "012345"));
+        let mut decoder = ByteDecoder::default();
+        let packets: Vec<Packet> = stream.iter().filter_map(|&b| decoder.feed(b)).collect();
+        assert_eq!(packets, [at(Status::Idle, 0), at(Status::Stopped, 12_345)]);
+    }
+
+    #[test]
+    fn pcm_both_polarities_and_rates() {
+        let mut stream = packet(b'A', "00000");
+        stream.extend(packet(b' ', "01234"));
+        stream.extend(packet(b'S', "012345"));
+        let expected = [
+            at(Status::Ready, 0),
+            at(Status::Running, 12_340),
+            at(Status::Stopped, 12_345),
+        ];
+        for sample_rate in [8000, 44100, 48000] {
+            for inverted in [false, true] {
+                let samples = samples(&bits(&stream), sample_rate, inverted);
+                assert_eq!(
+                    feed_pcm(&samples, sample_rate),
+                    expected,
+                    "{} Hz, inverted: {}",
+                    sample_rate,
+                    inverted
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pcm_drops_bad_checksums() {
+        let mut bad = packet(b' ', "00123");
+        bad[6] += 1;
+        let mut stream = bad;
+        stream.extend(packet(b' ', "00124"));
+        let samples = samples(&bits(&stream), 44100, false);
+        assert_eq!(feed_pcm(&samples, 44100), [at(Status::Running, 1_240)]);
+    }
+
+    #[test]
+    fn decodes_a_solve_from_a_recording() {
+        // what App::on_stackmat sees: reset, the running time, then the
+        // stopped time
+        let mut stream = packet(b'I', "00000");
+        stream.extend(packet(b'A', "00000"));
+        for digits in ["00010", "00120", "01230"] {
+            stream.extend(packet(b' ', digits));
+        }
+        stream.extend(packet(b'S', "01234"));
+        stream.extend(packet(b'S', "01234"));
+        let recording: Vec<u8> = samples(&bits(&stream), 48000, true)
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        let path =
+            std::env::temp_dir().join(format!("cursed-timer-stackmat-{}.raw", std::process::id()));
+        std::fs::write(&path, recording).unwrap();
+
+        let config = StackmatConfig {
+            source: path.to_string_lossy().to_string(),
+            format: Format::Pcm,
+            sample_rate: 48000,
+        };
+        let (tx, rx) = std::sync::mpsc::channel();
+        Stackmat(config.clone()).run(&tx).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // repeated packets are only sent once
+        let packets: Vec<Packet> = rx
+            .try_iter()
+            .map(|event| match event {
+                InputEvent::Stackmat(packet) => packet,
+                _ => panic!("not a Stackmat packet"),
+            })
+            .collect();
+        assert_eq!(
+            packets,
+            [
+                at(Status::Idle, 0),
+                at(Status::Ready, 0),
+                at(Status::Running, 100),
+                at(Status::Running, 1_200),
+                at(Status::Running, 12_300),
+                at(Status::Stopped, 12_340),
+            ]
+        );
+
+        // the callback stops the decoding
+        let mut first = None;
+        std::fs::write(&path, packet(b'S', "01234").repeat(3)).unwrap();
+        let serial = StackmatConfig {
+            format: Format::Serial,
+            ..config
+        };
+        decode(&serial, |packet| {
+            first = Some(packet);
+            false
+        })
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(first, Some(at(Status::Stopped, 12_340)));
+    }
+}
@@ -0,0 +1,395 @@
+use std::fmt;
+use std::str;
+use std::time::Duration;
+
+use chrono::prelude::*;
+use serde_derive::Deserialize;
+
+use crate::{
+    history::SolveTime,
+    scramble::{Puzzle, Scramble},
+    trainer::{self, AlgSet},
+};
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LibraryConfig {
+    /// Seconds a drill may take before its recall counts as one step worse
+    pub target_time: u64,
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        LibraryConfig { target_time: 3 }
+    }
+}
+
+/// How well the algorithm of a drilled case was remembered
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Recall {
+    Forgot,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Recall {
+    /// Response quality from 0 to 5 as used by SM-2
+    fn quality(&self) -> f64 {
+        match self {
+            Recall::Forgot => 1.0,
+            Recall::Hard => 3.0,
+            Recall::Good => 4.0,
+            Recall::Easy => 5.0,
+        }
+    }
+
+    fn worse(&self) -> Recall {
+        match self {
+            Recall::Easy => Recall::Good,
+            Recall::Good | Recall::Hard => Recall::Hard,
+            Recall::Forgot => Recall::Forgot,
+        }
+    }
+}
+
+/// Spaced repetition schedule of a case, following SM-2
+#[derive(Clone)]
+pub struct Review {
+    pub ease: f64,
+    /// Days between the last drill and the next one
+    pub interval: u32,
+    /// Drills in a row that weren't forgotten
+    pub streak: u32,
+    pub due: DateTime<Utc>,
+    pub last_time: Option<Duration>,
+}
+
+impl Default for Review {
+    fn default() -> Self {
+        Review {
+            ease: 2.5,
+            interval: 0,
+            streak: 0,
+            due: Utc::now(),
+            last_time: None,
+        }
+    }
+}
+
+impl Review {
+    /// Schedules the next drill after one that took `time`, a drill slower
+    /// than `target` counts as recalled one step worse
+    pub fn update(&mut self, recall: Recall, time: Duration, target: Duration) {
+        let recall = if time > target {
+            recall.worse()
+        } else {
+            recall
+        };
+        let quality = recall.quality();
+        if recall == Recall::Forgot {
+            // comes back in the same session
+            self.streak = 0;
+            self.interval = 0;
+        } else {
+            self.streak += 1;
+            self.interval = match self.streak {
+                1 => 1,
+                2 => 6,
+                _ => (self.interval as f64 * self.ease).round() as u32,
+            };
+        }
+        self.ease = (self.ease + 0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02)).max(1.3);
+        self.due = Utc::now() + chrono::Duration::days(self.interval as i64);
+        self.last_time = Some(time);
+    }
+}
+
+/// A case of the library and the user's algorithms for it
+#[derive(Clone)]
+pub struct LibraryCase {
+    pub puzzle: Puzzle,
+    /// Set the case belongs to, e.g. PLL or any name
+    pub set: String,
+    pub name: String,
+    pub algs: Vec<Scramble>,
+    pub review: Review,
+}
+
+impl LibraryCase {
+    /// Name including the puzzle and the set, e.g. 3x3 PLL Ua
+    pub fn id(&self) -> String {
+        format!("{} {} {}", self.puzzle, self.set, self.name)
+    }
+
+    /// Setup undoing the first algorithm, seen from a random side
+    pub fn scramble(&self) -> Scramble {
+        match self.algs.first() {
+            Some(alg) => trainer::random_aufs(&alg.inverse()),
+            None => Scramble::default(),
+        }
+    }
+}
+
+impl fmt::Display for LibraryCase {
+    // fmt: 3x3 PLL Ua: M2 U M U2 M' U M2; R U' R U R U R U' R' U' R2
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}",
+            self.id(),
+            self.algs
+                .iter()
+                .map(|alg| alg.to_string())
+                .collect::<Vec<String>>()
+                .join("; ")
+        )
+    }
+}
+
+impl str::FromStr for LibraryCase {
+    type Err = String;
+    // fmt: [puzzle] set case: alg[; alg], the puzzle defaults to the 3x3
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (case, algs) = s
+            .split_once(':')
+            .ok_or("Separate the case from its algorithms with ':'")?;
+        let mut words = case.split_whitespace().peekable();
+        let puzzle = match words.peek().map(|word| word.parse::<Puzzle>()) {
+            Some(Ok(puzzle)) => {
+                words.next();
+                puzzle
+            }
+            _ => Puzzle::Cube3,
+        };
+        let set = words.next().ok_or("Name the set of the case, e.g. PLL")?;
+        let name = words.collect::<Vec<&str>>().join(" ");
+        if name.is_empty() {
+            return Err(String::from("Name the case, e.g. PLL Ua"));
+        }
+        let algs = algs
+            .split(';')
+            .filter(|alg| !alg.trim().is_empty())
+            .enumerate()
+            .map(|(i, alg)| {
+                alg.parse::<Scramble>()
+                    .map_err(|err| format!("Algorithm {}: {}", i + 1, err))
+            })
+            .collect::<Result<Vec<Scramble>, String>>()?;
+        if algs.is_empty() {
+            return Err(String::from("Enter at least one algorithm"));
+        }
+        Ok(LibraryCase {
+            puzzle,
+            set: String::from(set),
+            name,
+            algs,
+            review: Review::default(),
+        })
+    }
+}
+
+/// Case being drilled and the setup shown for it
+pub struct Drill {
+    pub case: usize,
+    pub setup: Scramble,
+}
+
+/// The user's algorithms, per puzzle and case set
+#[derive(Default)]
+pub struct Library {
+    cases: Vec<LibraryCase>,
+}
+
+fn parse_record(record: &csv::StringRecord) -> Option<LibraryCase> {
+    let case: LibraryCase = format!(
+        "{} {} {}: {}",
+        &record[0], &record[1], &record[2], &record[3]
+    )
+    .parse()
+    .ok()?;
+    Some(LibraryCase {
+        review: Review {
+            ease: record.get(4)?.parse().ok()?,
+            interval: record.get(5)?.parse().ok()?,
+            streak: record.get(6)?.parse().ok()?,
+            due: record.get(7)?.parse().ok()?,
+            last_time: match record.get(8)? {
+                "" => None,
+                time => Some(time.parse::<SolveTime>().ok()?.0),
+            },
+        },
+        ..case
+    })
+}
+
+impl Library {
+    /// Loads the library, leaving out rows that can't be read
+    pub fn from_csv(file_path: &str) -> Library {
+        let mut library = Library::default();
+        if let Ok(mut reader) = csv::Reader::from_path(file_path) {
+            library.cases = reader
+                .records()
+                .flatten()
+                .filter_map(|record| parse_record(&record))
+                .collect();
+        }
+        library
+    }
+
+    pub fn save_csv(&self, file_path: &str) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(file_path)?;
+        writer.write_record([
+            "puzzle",
+            "set",
+            "case",
+            "algs",
+            "ease",
+            "interval",
+            "streak",
+            "due",
+            "last_time",
+        ])?;
+        for case in &self.cases {
+            writer.write_record(&[
+                case.puzzle.to_string(),
+                case.set.clone(),
+                case.name.clone(),
+                case.algs
+                    .iter()
+                    .map(|alg| alg.to_string())
+                    .collect::<Vec<String>>()
+                    .join("; "),
+                case.review.ease.to_string(),
+                case.review.interval.to_string(),
+                case.review.streak.to_string(),
+                case.review.due.to_string(),
+                case.review
+                    .last_time
+                    .map(|time| SolveTime(time).to_string())
+                    .unwrap_or_default(),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn cases(&self) -> &[LibraryCase] {
+        &self.cases
+    }
+
+    /// Adds the case, or replaces the algorithms of the case with the same
+    /// name keeping its schedule. Returns the index of the case.
+    pub fn add(&mut self, case: LibraryCase) -> usize {
+        match self.cases.iter().position(|c| c.id() == case.id()) {
+            Some(i) => {
+                self.cases[i].algs = case.algs;
+                i
+            }
+            None => {
+                self.cases.push(case);
+                self.cases.len() - 1
+            }
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.cases.len() {
+            self.cases.remove(index);
+        }
+    }
+
+    /// Adds the cases of a trainer set that aren't in the library yet, with
+    /// the trainer's algorithm. Returns the number of cases added.
+    pub fn import(&mut self, set: AlgSet) -> usize {
+        let before = self.cases.len();
+        for case in set.cases() {
+            let case = LibraryCase {
+                puzzle: Puzzle::Cube3,
+                set: set.to_string(),
+                name: case.name.clone(),
+                algs: vec![case.setup.inverse()],
+                review: Review::default(),
+            };
+            if !self.cases.iter().any(|c| c.id() == case.id()) {
+                self.cases.push(case);
+            }
+        }
+        self.cases.len() - before
+    }
+
+    /// Cases due for a drill, the most overdue first
+    pub fn due(&self) -> Vec<usize> {
+        let now = Utc::now();
+        let mut due: Vec<usize> = (0..self.cases.len())
+            .filter(|&i| self.cases[i].review.due <= now)
+            .collect();
+        due.sort_by_key(|&i| self.cases[i].review.due);
+        due
+    }
+
+    pub fn review(&mut self, index: usize, recall: Recall, time: Duration, target: Duration) {
+        if let Some(case) = self.cases.get_mut(index) {
+            case.review.update(recall, time, target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: Duration = Duration::from_secs(3);
+
+    fn drill(review: &mut Review, recall: Recall, secs: u64) {
+        review.update(recall, Duration::from_secs(secs), TARGET);
+    }
+
+    fn assert_ease(review: &Review, ease: f64) {
+        assert!((review.ease - ease).abs() < 1e-9, "ease {}", review.ease);
+    }
+
+    #[test]
+    fn review_intervals_grow_with_ease() {
+        let mut review = Review::default();
+        drill(&mut review, Recall::Good, 1);
+        assert_eq!((review.streak, review.interval), (1, 1));
+        assert_ease(&review, 2.5);
+        drill(&mut review, Recall::Good, 1);
+        assert_eq!((review.streak, review.interval), (2, 6));
+        drill(&mut review, Recall::Good, 1);
+        assert_eq!((review.streak, review.interval), (3, 15));
+        assert_eq!(review.last_time, Some(Duration::from_secs(1)));
+        let days = (review.due - Utc::now()).num_hours() as f64 / 24.0;
+        assert!((days - 15.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn review_ease_follows_recall() {
+        let mut review = Review::default();
+        drill(&mut review, Recall::Easy, 1);
+        assert_ease(&review, 2.6);
+        drill(&mut review, Recall::Hard, 1);
+        assert_ease(&review, 2.46);
+        // a slow drill counts as recalled one step worse
+        drill(&mut review, Recall::Easy, 5);
+        assert_ease(&review, 2.46);
+    }
+
+    #[test]
+    fn review_forgot_starts_over() {
+        let mut review = Review::default();
+        drill(&mut review, Recall::Good, 1);
+        drill(&mut review, Recall::Good, 1);
+        drill(&mut review, Recall::Forgot, 1);
+        assert_eq!((review.streak, review.interval), (0, 0));
+        assert_ease(&review, 1.96);
+        assert!(review.due <= Utc::now());
+        for _ in 0..5 {
+            drill(&mut review, Recall::Forgot, 1);
+        }
+        assert_ease(&review, 1.3);
+        drill(&mut review, Recall::Good, 1);
+        assert_eq!((review.streak, review.interval), (1, 1));
+    }
+}
@@ -1,25 +1,120 @@
 use app::App;
+use cli::Command;
+use config::CubeConfig;
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use scramble_set::ScrambleSet;
+use std::fs;
 use std::io;
+use std::path::Path;
 use tui::{backend::CrosstermBackend, Terminal};
 
 #[cfg(feature = "debug")]
 use std::fs::OpenOptions;
 
 mod app;
+mod cli;
 mod config;
 mod countdown;
+mod cube;
 mod history;
+mod input;
+mod library;
+mod plan;
+mod reconstruction;
+mod round;
 mod scramble;
+mod scramble_set;
+mod settings;
+mod solver;
 mod stats;
 mod timer;
+mod trainer;
 mod ui;
 
 fn main() -> io::Result<()> {
+    let args = match cli::parse() {
+        Ok(Command::Run(args)) => args,
+        Ok(Command::StackmatReplay(config)) => {
+            return input::stackmat::decode(&config, |packet| {
+                println!("{}", packet);
+                true
+            });
+        }
+        Ok(Command::ScrambleSet(args)) => {
+            if let Some(seed) = args.seed {
+                scramble::seed(seed);
+            }
+            let name = Path::new(&args.path)
+                .file_stem()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let set = ScrambleSet::generate(&name, args.puzzle, args.count, args.length);
+            fs::write(&args.path, set.to_string())?;
+            println!(
+                "Wrote {} {} scrambles to {}",
+                args.count, args.puzzle, args.path
+            );
+            return Ok(());
+        }
+        Ok(Command::Solve(scramble)) => {
+            let solution = solver::solve(&scramble, solver::SOLVE_TIME);
+            println!(
+                "Solution ({} moves): {}",
+                solution.moves.moves().len(),
+                solution.moves
+            );
+            for (method, steps) in &solution.steps {
+                println!("\n{}", method);
+                for step in steps {
+                    println!(
+                        "    {}: {} ({})",
+                        step.name,
+                        step.moves,
+                        step.moves.moves().len()
+                    );
+                }
+            }
+            return Ok(());
+        }
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(profile) = &args.profile {
+        CubeConfig::set_profile(profile);
+    }
+    let config = match CubeConfig::migrate().and_then(|_| CubeConfig::new()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let stackmat = args.stackmat.as_ref().or(config.stackmat.as_ref());
+    let smartcube = args.smartcube.as_ref().or(config.smartcube.as_ref());
+    let sources = [
+        stackmat.map(|stackmat| stackmat.source.as_str()),
+        smartcube.map(|smartcube| smartcube.source.as_str()),
+    ];
+    if let Err(err) = sources
+        .into_iter()
+        .flatten()
+        .try_for_each(input::check_source)
+    {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
     #[cfg(feature = "debug")]
     tracing_subscriber::fmt()
         .with_writer(
@@ -30,6 +125,14 @@ fn main() -> io::Result<()> {
         )
         .init();
 
+    let mut app = match App::new("Rubik Cube Timer", args, config) {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture,)?;
@@ -37,7 +140,6 @@ fn main() -> io::Result<()> {
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
-    let mut app = App::new("Rubik Cube Timer");
     let res = app.run(&mut terminal);
 
     // restore terminal
@@ -52,6 +154,9 @@ fn main() -> io::Result<()> {
     if let Err(err) = res {
         println!("{:?}", err);
     }
+    if let Some(notice) = app.read_only_notice() {
+        eprintln!("{}", notice);
+    }
 
     Ok(())
 }
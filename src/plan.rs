@@ -0,0 +1,190 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use serde_derive::Deserialize;
+
+use crate::{
+    cube::{self, Cube, Vec3},
+    scramble::Scramble,
+    solver,
+};
+
+/// Longest optimal solution searched for, beyond any of the goals' worst case
+const MAX_DEPTH: usize = 12;
+
+/// First step of a solve planned during inspection, always on the D side
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    #[serde(rename = "cross")]
+    Cross,
+    /// Cross with every edge oriented, the ZZ start
+    #[serde(rename = "eocross")]
+    EoCross,
+    /// Roux 1x2x3 block on the left
+    #[serde(rename = "firstblock")]
+    FirstBlock,
+    /// Petrus 2x2x2 block in the down back left corner
+    #[serde(rename = "2x2x2")]
+    Block,
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Goal::Cross => "Cross",
+            Goal::EoCross => "EOCross",
+            Goal::FirstBlock => "First Block",
+            Goal::Block => "2x2x2",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct PlanConfig {
+    pub goal: Goal,
+    /// Optimal solutions shown next to the planned one
+    pub solutions: usize,
+}
+
+impl Default for PlanConfig {
+    fn default() -> Self {
+        PlanConfig {
+            goal: Goal::Cross,
+            solutions: 5,
+        }
+    }
+}
+
+/// Followed stickers of the goal's pieces and the tables estimating how many
+/// moves are left to solve them
+struct Solver {
+    /// Followed sticker of each piece when solved, every edge for EOCross
+    solved: Vec<u8>,
+    /// Pieces of each table by their index in `solved`, and the table
+    tables: Vec<(Vec<usize>, Vec<u8>)>,
+    /// Moves left to orient every edge, by `solver::edge_orientation`
+    orientation: Option<Vec<u8>>,
+}
+
+impl Solver {
+    fn new(goal: Goal) -> Self {
+        let followed = |pieces: &[Vec3]| -> Vec<u8> {
+            pieces
+                .iter()
+                .filter_map(|&pos| {
+                    let (_, stickers) = cube::pieces().iter().find(|(p, _)| *p == pos)?;
+                    Some(stickers[0] as u8)
+                })
+                .collect()
+        };
+        let cross = [[0, -1, 1], [1, -1, 0], [0, -1, -1], [-1, -1, 0]];
+        let (solved, groups) = match goal {
+            Goal::Cross => (followed(&cross), vec![vec![0, 1, 2, 3]]),
+            Goal::EoCross => {
+                let edges: Vec<Vec3> = cube::pieces()
+                    .iter()
+                    .filter(|(_, stickers)| stickers.len() == 2)
+                    .map(|(pos, _)| *pos)
+                    .filter(|pos| !cross.contains(pos))
+                    .collect();
+                (
+                    followed(&[&cross[..], &edges].concat()),
+                    vec![vec![0, 1, 2, 3]],
+                )
+            }
+            // DL, FL and BL edges, then the DFL and DBL corners
+            Goal::FirstBlock => (
+                followed(&[
+                    [-1, -1, 0],
+                    [-1, 0, 1],
+                    [-1, 0, -1],
+                    [-1, -1, 1],
+                    [-1, -1, -1],
+                ]),
+                vec![vec![0, 1, 2], vec![0, 3, 4]],
+            ),
+            Goal::Block => (
+                followed(&[[-1, -1, 0], [0, -1, -1], [-1, 0, -1], [-1, -1, -1]]),
+                vec![vec![0, 1, 2, 3]],
+            ),
+        };
+        let tables = groups
+            .into_iter()
+            .map(|pieces| {
+                let stickers: Vec<u8> = pieces.iter().map(|&i| solved[i]).collect();
+                let size = solver::pattern_size(stickers.len());
+                let table =
+                    solver::pattern_table(&stickers, solver::turns(), size, solver::pattern_index);
+                (pieces, table)
+            })
+            .collect();
+        let orientation = (goal == Goal::EoCross).then(|| {
+            solver::pattern_table(&solved, solver::turns(), 1 << 12, |stickers| {
+                solver::edge_orientation(&solved, stickers)
+            })
+        });
+        Solver {
+            solved,
+            tables,
+            orientation,
+        }
+    }
+
+    /// Lower bound of the moves left, 0 only once the goal is solved
+    fn estimate(&self, stickers: &[u8]) -> usize {
+        let pieces = self.tables.iter().map(|(pieces, table)| {
+            let stickers: Vec<u8> = pieces.iter().map(|&i| stickers[i]).collect();
+            table[solver::pattern_index(&stickers)]
+        });
+        let orientation = self
+            .orientation
+            .iter()
+            .map(|table| table[solver::edge_orientation(&self.solved, stickers)]);
+        pieces.chain(orientation).max().unwrap_or(0) as usize
+    }
+}
+
+impl Goal {
+    const ALL: [Goal; 4] = [Goal::Cross, Goal::EoCross, Goal::FirstBlock, Goal::Block];
+
+    /// Whether the piece solved at `pos` is part of the goal
+    fn in_block(&self, [x, y, z]: Vec3) -> bool {
+        match self {
+            Goal::Cross | Goal::EoCross => y == -1 && (x == 0 || z == 0),
+            Goal::FirstBlock => x == -1 && y <= 0,
+            Goal::Block => x <= 0 && y <= 0 && z <= 0,
+        }
+    }
+
+    /// The goal is solved on `cube`, relative to its centers
+    pub fn is_solved(&self, cube: &Cube) -> bool {
+        cube.pieces_solved(|pos| self.in_block(pos))
+            && (*self != Goal::EoCross || cube.edges_oriented())
+    }
+
+    /// Pattern tables of the goal, built the first time they are needed
+    fn solver(&self) -> &'static Solver {
+        static SOLVERS: [OnceLock<Solver>; 4] = [
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+        ];
+        let index = Goal::ALL.iter().position(|goal| goal == self).unwrap();
+        SOLVERS[index].get_or_init(|| Solver::new(*self))
+    }
+
+    /// Up to `max` of the shortest face turn solutions of the goal on `cube`
+    pub fn solutions(&self, cube: &Cube, max: usize) -> Vec<Scramble> {
+        let solver = self.solver();
+        let stickers = solver::locate(cube, &solver.solved);
+        solver::optimal(&stickers, solver::turns(), MAX_DEPTH, max, |stickers| {
+            solver.estimate(stickers)
+        })
+        .into_iter()
+        .map(Scramble::from)
+        .collect()
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+use std::str;
+use std::time::Duration;
+
+use crate::{
+    cube::Cube,
+    scramble::{self, Move, ParseMoveError, Scramble},
+};
+
+#[derive(Clone, Copy)]
+pub struct TimedMove {
+    pub m: Move,
+    /// Time since the start of the solve, unknown for typed in moves
+    pub at: Option<Duration>,
+}
+
+impl fmt::Display for TimedMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.at {
+            Some(at) => write!(f, "{}@{}", self.m, at.as_millis()),
+            None => write!(f, "{}", self.m),
+        }
+    }
+}
+
+impl str::FromStr for TimedMove {
+    type Err = ParseMoveError;
+    // fmt: R'@1234 (milliseconds since the start of the solve) or R'
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (m, at) = match s.split_once('@') {
+            Some((m, at)) => (m, Some(at)),
+            None => (s, None),
+        };
+        Ok(TimedMove {
+            m: m.parse()?,
+            at: match at {
+                Some(at) => Some(Duration::from_millis(at.parse().map_err(|_| {
+                    ParseMoveError::new(s, m.chars().count() + 1, "Move timestamp isn't valid")
+                })?)),
+                None => None,
+            },
+        })
+    }
+}
+
+/// Moves of a solve, either recorded from a smart cube or typed in
+#[derive(Clone, Default)]
+pub struct Reconstruction(pub Vec<TimedMove>);
+
+impl fmt::Display for Reconstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        )
+    }
+}
+
+impl str::FromStr for Reconstruction {
+    type Err = ParseMoveError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        scramble::tokens(s)
+            .map(|(offset, m)| m.parse::<TimedMove>().map_err(|err| err.offset(offset)))
+            .collect::<Result<Vec<TimedMove>, _>>()
+            .map(Reconstruction)
+    }
+}
+
+pub struct Step {
+    pub name: String,
+    pub moves: usize,
+    pub duration: Option<Duration>,
+}
+
+impl Reconstruction {
+    pub fn push(&mut self, m: Move, at: Option<Duration>) {
+        self.0.push(TimedMove { m, at })
+    }
+
+    /// Moves in the half turn metric, see `Move::turns`
+    pub fn move_count(&self) -> usize {
+        self.0.iter().map(|m| m.m.turns()).sum()
+    }
+
+    /// Turns per second over the whole solve
+    pub fn tps(&self, time: Duration) -> f64 {
+        if time.is_zero() {
+            return 0.0;
+        }
+        self.move_count() as f64 / time.as_secs_f64()
+    }
+
+    /// Splits the solve into CFOP steps by replaying it on the scrambled cube.
+    /// The cross face is whichever cross gets solved first, steps that never
+    /// complete are left out.
+    pub fn cfop_steps(&self, scramble: &Scramble) -> Vec<Step> {
+        let mut cube = Cube::scrambled(scramble);
+        let mut done = vec![];
+        let mut cross: Option<usize> = None;
+        let mut pairs = 0;
+        let mut oll = false;
+        for i in 0..=self.0.len() {
+            if i > 0 {
+                cube.apply(&self.0[i - 1].m);
+            }
+            let face = match cross {
+                Some(face) => face,
+                None => match (0..6).find(|&f| cube.cross_solved(f)) {
+                    Some(face) => {
+                        cross = Some(face);
+                        done.push((String::from("Cross"), i));
+                        face
+                    }
+                    None => continue,
+                },
+            };
+            if !cube.cross_solved(face) {
+                continue;
+            }
+            let solved_pairs = cube.f2l_pairs(face);
+            while pairs < solved_pairs {
+                pairs += 1;
+                done.push((format!("F2L {}", pairs), i));
+            }
+            if pairs == 4 && !oll && cube.face_solved((face + 3) % 6) {
+                oll = true;
+                done.push((String::from("OLL"), i));
+            }
+            if oll && cube.is_solved() {
+                done.push((String::from("PLL"), i));
+                break;
+            }
+        }
+
+        let at = |i: usize| -> Option<Duration> {
+            match i {
+                0 => Some(Duration::from_secs(0)),
+                _ => self.0[i - 1].at,
+            }
+        };
+        let mut start = 0;
+        done.into_iter()
+            .map(|(name, end)| {
+                let step = Step {
+                    name,
+                    moves: self.0[start..end].iter().map(|m| m.m.turns()).sum(),
+                    duration: at(end).zip(at(start)).map(|(e, s)| e.saturating_sub(s)),
+                };
+                start = end;
+                step
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_moves_in_htm() {
+        let solve: Reconstruction = "y R U@120 M2'@400 Rw@650 x'".parse().unwrap();
+        assert_eq!(solve.move_count(), 5);
+        assert_eq!(solve.tps(Duration::from_secs(1)), 5.0);
+        assert_eq!(solve.to_string(), "y R U@120 M2@400 Rw@650 x'");
+    }
+
+    #[test]
+    fn timestamps_are_checked() {
+        let err = "R U@1x".parse::<Reconstruction>().err().unwrap();
+        assert_eq!(
+            err,
+            ParseMoveError::new("U@1x", 4, "Move timestamp isn't valid")
+        );
+    }
+}
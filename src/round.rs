@@ -0,0 +1,266 @@
+use std::fmt;
+use std::time::Duration;
+
+use serde_derive::Deserialize;
+
+use crate::history::{Entry, Penalty};
+
+/// How the official result of a round is computed
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundFormat {
+    /// Average of 5, the best and the worst solve are dropped
+    Ao5,
+    /// Mean of 3
+    Mo3,
+    /// Best of 3
+    Bo3,
+}
+
+impl fmt::Display for RoundFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            RoundFormat::Ao5 => "Ao5",
+            RoundFormat::Mo3 => "Mo3",
+            RoundFormat::Bo3 => "Bo3",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl RoundFormat {
+    pub fn solves(&self) -> usize {
+        match self {
+            RoundFormat::Ao5 => 5,
+            RoundFormat::Mo3 | RoundFormat::Bo3 => 3,
+        }
+    }
+}
+
+/// A competition round as held by the WCA
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct RoundConfig {
+    pub format: RoundFormat,
+    /// Seconds each solve may take before it is a DNF
+    pub time_limit: Option<u64>,
+    /// Seconds all solves of the round may take together
+    pub cumulative_limit: Option<u64>,
+    /// Seconds one of the first `cutoff_solves` has to beat to finish the round
+    pub cutoff: Option<u64>,
+    pub cutoff_solves: usize,
+}
+
+impl Default for RoundConfig {
+    fn default() -> Self {
+        RoundConfig {
+            format: RoundFormat::Ao5,
+            time_limit: None,
+            cumulative_limit: None,
+            cutoff: None,
+            cutoff_solves: 2,
+        }
+    }
+}
+
+/// Result of a solve, None for a DNF or DNS
+fn result(entry: &Entry) -> Option<Duration> {
+    match entry.penalty {
+        Penalty::No | Penalty::Time => Some(entry.time.0),
+        _ => None,
+    }
+}
+
+impl RoundConfig {
+    /// One of the solves before the cutoff beat it, None until that's known
+    pub fn made_cutoff(&self, solves: &[&Entry]) -> Option<bool> {
+        let cutoff = match self.cutoff {
+            Some(cutoff) => Duration::from_secs(cutoff),
+            None => return Some(true),
+        };
+        let made = solves
+            .iter()
+            .take(self.cutoff_solves)
+            .filter_map(|entry| result(entry))
+            .any(|time| time < cutoff);
+        match made || solves.len() >= self.cutoff_solves {
+            true => Some(made),
+            false => None,
+        }
+    }
+
+    /// Time the next solve may take, if it is limited
+    pub fn limit(&self, solves: &[&Entry]) -> Option<Duration> {
+        let used = solves
+            .iter()
+            .fold(Duration::from_secs(0), |a, entry| a + entry.time.0);
+        let cumulative = self
+            .cumulative_limit
+            .map(|limit| Duration::from_secs(limit).saturating_sub(used));
+        let single = self.time_limit.map(Duration::from_secs);
+        match (single, cumulative) {
+            (Some(single), Some(cumulative)) => Some(single.min(cumulative)),
+            _ => single.or(cumulative),
+        }
+    }
+
+    /// Every solve is done, the cutoff was missed or the time ran out
+    pub fn is_over(&self, solves: &[&Entry]) -> bool {
+        solves.len() >= self.format.solves()
+            || self.made_cutoff(solves) == Some(false)
+            || self.limit(solves).is_some_and(|limit| limit.is_zero())
+    }
+
+    /// Best single and the official average of the round, None for a DNF.
+    /// The average is only there once every solve of the format is done.
+    pub fn result(&self, solves: &[&Entry]) -> (Option<Duration>, Option<Option<Duration>>) {
+        let mut results: Vec<Option<Duration>> = solves.iter().map(|entry| result(entry)).collect();
+        let single = results.iter().flatten().min().copied();
+        if results.len() < self.format.solves() {
+            return (single, None);
+        }
+        // DNFs sort last
+        results.sort_by_key(|result| result.unwrap_or(Duration::MAX));
+        let mean = |results: &[Option<Duration>]| {
+            results
+                .iter()
+                .copied()
+                .collect::<Option<Vec<Duration>>>()
+                .map(|times| times.iter().sum::<Duration>() / times.len() as u32)
+        };
+        let average = match self.format {
+            RoundFormat::Ao5 => mean(&results[1..4]),
+            RoundFormat::Mo3 => mean(&results),
+            RoundFormat::Bo3 => single,
+        };
+        (single, Some(average))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Mode, history::SolveTime};
+    use chrono::Utc;
+
+    /// Solves with their time in hundredths, negative for a DNF
+    fn with_times(times: &[i64]) -> Vec<Entry> {
+        times
+            .iter()
+            .map(|&time| Entry {
+                time: SolveTime(Duration::from_millis(time.unsigned_abs() * 10)),
+                scramble: "R U".parse().unwrap(),
+                date: Utc::now(),
+                penalty: match time < 0 {
+                    true => Penalty::DNF,
+                    false => Penalty::No,
+                },
+                moves: None,
+                splits: vec![],
+                scrambles: vec![],
+                multi: None,
+                mode: Mode::CompSim,
+                round: Some(1),
+                case: None,
+                optimal: None,
+            })
+            .collect()
+    }
+
+    fn round(format: RoundFormat) -> RoundConfig {
+        RoundConfig {
+            format,
+            ..RoundConfig::default()
+        }
+    }
+
+    fn hundredths(hundredths: u64) -> Option<Duration> {
+        Some(Duration::from_millis(hundredths * 10))
+    }
+
+    #[test]
+    fn ao5_drops_best_and_worst() {
+        let entries = with_times(&[1200, 1000, 1500, 1100, 1300]);
+        let solves: Vec<&Entry> = entries.iter().collect();
+        let ao5 = round(RoundFormat::Ao5);
+        assert_eq!(ao5.result(&solves[..4]), (hundredths(1000), None));
+        assert_eq!(
+            ao5.result(&solves),
+            (hundredths(1000), Some(hundredths(1200)))
+        );
+
+        // a single DNF is dropped as the worst, two make the average a DNF
+        let entries = with_times(&[1200, -1000, 1400, 1100, 1300]);
+        let solves: Vec<&Entry> = entries.iter().collect();
+        assert_eq!(
+            ao5.result(&solves),
+            (hundredths(1100), Some(hundredths(1300)))
+        );
+        let entries = with_times(&[1200, -1000, 1500, -1100, 1300]);
+        let solves: Vec<&Entry> = entries.iter().collect();
+        assert_eq!(ao5.result(&solves), (hundredths(1200), Some(None)));
+    }
+
+    #[test]
+    fn mo3_and_bo3() {
+        let entries = with_times(&[1200, -900, 1500]);
+        let solves: Vec<&Entry> = entries.iter().collect();
+        assert_eq!(
+            round(RoundFormat::Mo3).result(&solves),
+            (hundredths(1200), Some(None))
+        );
+        assert_eq!(
+            round(RoundFormat::Bo3).result(&solves),
+            (hundredths(1200), Some(hundredths(1200)))
+        );
+        let entries = with_times(&[1200, 900, 1500]);
+        let solves: Vec<&Entry> = entries.iter().collect();
+        assert_eq!(
+            round(RoundFormat::Mo3).result(&solves),
+            (hundredths(900), Some(hundredths(1200)))
+        );
+    }
+
+    #[test]
+    fn cutoff() {
+        let config = RoundConfig {
+            cutoff: Some(15),
+            ..RoundConfig::default()
+        };
+        let entries = with_times(&[1600, 1400, 1700, 1800]);
+        let solves: Vec<&Entry> = entries.iter().collect();
+        assert_eq!(config.made_cutoff(&[]), None);
+        assert_eq!(config.made_cutoff(&solves[..1]), None);
+        assert_eq!(config.made_cutoff(&solves[..2]), Some(true));
+        assert!(!config.is_over(&solves[..4]));
+
+        // only the first solves count, a DNF never makes it
+        let entries = with_times(&[-1000, 1600, 1400]);
+        let solves: Vec<&Entry> = entries.iter().collect();
+        assert_eq!(config.made_cutoff(&solves), Some(false));
+        assert!(config.is_over(&solves[..2]));
+        assert_eq!(RoundConfig::default().made_cutoff(&[]), Some(true));
+    }
+
+    #[test]
+    fn limits() {
+        let config = RoundConfig {
+            time_limit: Some(60),
+            cumulative_limit: Some(100),
+            ..RoundConfig::default()
+        };
+        assert_eq!(config.limit(&[]), Some(Duration::from_secs(60)));
+        let entries = with_times(&[5000, 3000]);
+        let solves: Vec<&Entry> = entries.iter().collect();
+        assert_eq!(config.limit(&solves[..1]), Some(Duration::from_secs(50)));
+        assert_eq!(config.limit(&solves), Some(Duration::from_secs(20)));
+        assert!(!config.is_over(&solves));
+
+        // DNFs use up the cumulative limit too
+        let entries = with_times(&[6000, -4500]);
+        let solves: Vec<&Entry> = entries.iter().collect();
+        assert_eq!(config.limit(&solves), Some(Duration::ZERO));
+        assert!(config.is_over(&solves));
+        assert_eq!(RoundConfig::default().limit(&solves), None);
+    }
+}
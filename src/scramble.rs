@@ -1,36 +1,223 @@
 use std::fmt;
 use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use rand::{
     distributions::{Distribution, Standard},
-    Rng,
+    rngs::StdRng,
+    seq::SliceRandom,
+    Rng, SeedableRng,
 };
+use serde_derive::Deserialize;
 
-#[derive(Clone)]
+use crate::{cube::Cube, solver};
+
+/// Source of every scramble, seeded to make the scramble sequence reproducible
+static RNG: Mutex<Option<StdRng>> = Mutex::new(None);
+
+/// Scrambles solvable in fewer moves are rejected
+static MIN_MOVES: AtomicUsize = AtomicUsize::new(2);
+
+/// Highest `min_moves`, the check searches every solution that short so it
+/// gets too slow beyond
+pub const MAX_MIN_MOVES: usize = 6;
+
+/// Rejects 3x3 scrambles that can be solved in fewer than `moves` moves, up
+/// to `MAX_MIN_MOVES`
+pub fn min_moves(moves: usize) {
+    MIN_MOVES.store(moves.min(MAX_MIN_MOVES), Ordering::Relaxed);
+}
+
+/// Makes the scrambles generated from now on the same for everyone using `seed`
+pub fn seed(seed: u64) {
+    *RNG.lock().unwrap() = Some(StdRng::seed_from_u64(seed));
+}
+
+/// Runs `f` with the generator behind every scramble, so that anything else
+/// random about a solve follows the seed too
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    let mut rng = RNG.lock().unwrap();
+    f(rng.get_or_insert_with(StdRng::from_entropy))
+}
+
+/// Puzzles that can be scrambled, e.g. for a relay
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Puzzle {
+    #[serde(rename = "2x2")]
+    Cube2,
+    #[serde(rename = "3x3")]
+    Cube3,
+    #[serde(rename = "4x4")]
+    Cube4,
+    #[serde(rename = "5x5")]
+    Cube5,
+}
+
+impl str::FromStr for Puzzle {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2x2" => Ok(Puzzle::Cube2),
+            "3x3" => Ok(Puzzle::Cube3),
+            "4x4" => Ok(Puzzle::Cube4),
+            "5x5" => Ok(Puzzle::Cube5),
+            _ => Err(format!(
+                "'{}' is not a puzzle, expected 2x2, 3x3, 4x4 or 5x5",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Puzzle::Cube2 => "2x2",
+            Puzzle::Cube3 => "3x3",
+            Puzzle::Cube4 => "4x4",
+            Puzzle::Cube5 => "5x5",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Puzzle {
+    /// Random scramble of the puzzle, `length` is only used for the 3x3
+    pub fn scramble(&self, length: usize) -> Scramble {
+        use Direction::*;
+        match self {
+            Puzzle::Cube2 => Scramble::random(11, &[Right, Up, Front], false),
+            Puzzle::Cube3 => Scramble::new_rand(length),
+            Puzzle::Cube4 => Scramble::random(40, &Direction::ALL, true),
+            Puzzle::Cube5 => Scramble::random(60, &Direction::ALL, true),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 pub struct Scramble(Vec<Move>);
 
 impl Scramble {
+    /// Random moves, rejecting scrambles that can be solved in fewer than
+    /// the minimum number of moves (see `min_moves`)
     pub fn new_rand(n: usize) -> Scramble {
-        let mut sequence: Vec<Move> = Vec::with_capacity(n);
+        let min_moves = MIN_MOVES.load(Ordering::Relaxed).min(n);
+        loop {
+            let scramble = Scramble::random(n, &Direction::ALL, false);
+            let cube = Cube::scrambled(&scramble);
+            if min_moves == 0 || solver::search(&cube, min_moves - 1, Cube::is_solved).is_none() {
+                return scramble;
+            }
+        }
+    }
 
-        while sequence.len() != n {
-            let direction = rand::random::<Direction>();
-            let modifier = rand::random::<Modifier>();
+    /// `n` moves turning the given faces, big cubes (`wide`) turn two
+    /// layers at random
+    fn random(n: usize, directions: &[Direction], wide: bool) -> Scramble {
+        with_rng(|rng| {
+            let mut sequence: Vec<Move> = Vec::with_capacity(n);
 
-            if let Some(l) = sequence.last() {
-                if l.direction == direction {
+            while sequence.len() != n {
+                let direction = *directions.choose(rng).unwrap();
+                if !Direction::can_follow(&sequence, direction) {
                     continue;
                 }
+                sequence.push(Move {
+                    direction,
+                    modifier: rng.gen(),
+                    layers: if wide && rng.gen() {
+                        Layers::Wide(2)
+                    } else {
+                        Layers::Outer
+                    },
+                });
+            }
+            Scramble(sequence)
+        })
+    }
+
+    /// Fewest moves scrambles are wrapped in R' U' F so that neither end
+    /// of the scramble can be cancelled by the solution
+    pub fn new_fmc(n: usize) -> Scramble {
+        let padding: Scramble = "R' U' F".parse().unwrap();
+        loop {
+            let filler = Scramble::new_rand(n.saturating_sub(6).max(1));
+            let mut sequence = padding.0.clone();
+            sequence.extend(filler.0);
+            sequence.extend(padding.0.iter().copied());
+            // the filler has to join the padding as any two moves would
+            let joined = (1..sequence.len())
+                .all(|i| Direction::can_follow(&sequence[..i], sequence[i].direction));
+            if joined {
+                return Scramble(sequence);
+            }
+        }
+    }
+
+    /// Blindfolded scrambles end in random wide moves so that the cube
+    /// can't be inspected in a known orientation
+    pub fn new_bld(n: usize) -> Scramble {
+        use Direction::*;
+        use Modifier::*;
+
+        let mut scramble = Scramble::new_rand(n);
+        let first = [
+            None,
+            Some((Right, No)),
+            Some((Right, Prime)),
+            Some((Right, Twice)),
+            Some((Front, No)),
+            Some((Front, Prime)),
+        ];
+        let second = [None, Some((Up, No)), Some((Up, Prime)), Some((Up, Twice))];
+        for choices in [&first[..], &second[..]] {
+            if let Some((direction, modifier)) = with_rng(|rng| *choices.choose(rng).unwrap()) {
+                scramble.0.push(Move {
+                    direction,
+                    modifier,
+                    layers: Layers::Wide(2),
+                });
+            }
+        }
+        scramble
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.0
+    }
+
+    /// Moves undoing the scramble
+    pub fn inverse(&self) -> Scramble {
+        Scramble(self.0.iter().rev().map(Move::inverse).collect())
+    }
+
+    /// The scramble followed by `other`, merging turns of the same layers
+    /// where they meet, e.g. `R U` then `U' R` gives `R2`
+    pub fn then(&self, other: &Scramble) -> Scramble {
+        let mut sequence = self.0.clone();
+        for &m in &other.0 {
+            match sequence.last() {
+                Some(last) if last.direction == m.direction && last.layers == m.layers => {
+                    let quarters = (last.modifier.quarters() + m.modifier.quarters()) % 4;
+                    sequence.pop();
+                    if let Some(modifier) = Modifier::from_quarters(quarters) {
+                        sequence.push(Move { modifier, ..m });
+                    }
+                }
+                _ => sequence.push(m),
             }
-            sequence.push(Move {
-                direction,
-                modifier,
-            });
         }
         Scramble(sequence)
     }
 }
 
+impl From<Vec<Move>> for Scramble {
+    fn from(moves: Vec<Move>) -> Self {
+        Scramble(moves)
+    }
+}
+
 impl fmt::Display for Scramble {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -45,60 +232,128 @@ impl fmt::Display for Scramble {
     }
 }
 
+/// Splits `s` on whitespace, with the character offset of each token
+pub fn tokens(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.split_whitespace().map(move |token| {
+        let offset = token.as_ptr() as usize - s.as_ptr() as usize;
+        (s[..offset].chars().count(), token)
+    })
+}
+
 impl str::FromStr for Scramble {
-    type Err = &'static str;
+    type Err = ParseMoveError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let strs = s.split(" ");
-        let mut scramble = Scramble(Vec::new());
-        for s in strs {
-            scramble.0.push(s.parse()?);
+        tokens(s)
+            .map(|(offset, token)| token.parse::<Move>().map_err(|err| err.offset(offset)))
+            .collect::<Result<Vec<Move>, _>>()
+            .map(Scramble)
+    }
+}
+
+/// A move that isn't valid notation and where in the text it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMoveError {
+    pub token: String,
+    /// Character offset of the problem from the start of the text
+    pub position: usize,
+    pub reason: &'static str,
+}
+
+impl ParseMoveError {
+    pub fn new(token: &str, position: usize, reason: &'static str) -> Self {
+        ParseMoveError {
+            token: String::from(token),
+            position,
+            reason,
         }
-        Ok(scramble)
+    }
+
+    /// Moves the position by the offset of the token in a longer text
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.position += offset;
+        self
     }
 }
 
-// impl Clone for Scramble {
-//     fn clone(&self) -> Scramble {
-//         let v = self.0;
-//         Scramble(v)
-//     }
-// }
+impl fmt::Display for ParseMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} in '{}' at column {}",
+            self.reason,
+            self.token,
+            self.position + 1
+        )
+    }
+}
 
 impl str::FromStr for Move {
-    type Err = &'static str;
+    type Err = ParseMoveError;
+    // fmt: WCA notation, e.g. R, U', F2, Rw, r, 3Rw2, M, E', S2, x, y', z2
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use Direction::*;
         use Modifier::*;
 
-        let mut cs = s.chars();
-        let direction = match cs.next() {
-            Some('F') => Front,
-            Some('B') => Back,
-            Some('D') => Down,
-            Some('U') => Up,
-            Some('R') => Right,
-            Some('L') => Left,
-            Some(_) => return Err("Move direction isn't valid"),
-            None => return Err("Move format is empty"),
+        let err = |position: usize, reason| ParseMoveError::new(s, position, reason);
+        let digits = s.chars().take_while(|c| c.is_ascii_digit()).count();
+        let count = match digits {
+            0 => None,
+            _ => match s[..digits].parse::<u8>() {
+                Ok(count) if count >= 2 => Some(count),
+                _ => return Err(err(0, "Wide moves turn 2 or more layers")),
+            },
         };
-        let modifier = match cs.next() {
-            Some('\'') => Prime,
-            Some('2') => Twice,
-            Some(_) => return Err("Move modifier isn't valid"),
-            None => No,
+
+        let mut cs = s.chars().skip(digits).peekable();
+        let (direction, layers) = match cs.next() {
+            Some('F') => (Front, Layers::Outer),
+            Some('B') => (Back, Layers::Outer),
+            Some('D') => (Down, Layers::Outer),
+            Some('U') => (Up, Layers::Outer),
+            Some('R') => (Right, Layers::Outer),
+            Some('L') => (Left, Layers::Outer),
+            Some('f') => (Front, Layers::Wide(2)),
+            Some('b') => (Back, Layers::Wide(2)),
+            Some('d') => (Down, Layers::Wide(2)),
+            Some('u') => (Up, Layers::Wide(2)),
+            Some('r') => (Right, Layers::Wide(2)),
+            Some('l') => (Left, Layers::Wide(2)),
+            Some('M') => (Left, Layers::Slice),
+            Some('E') => (Down, Layers::Slice),
+            Some('S') => (Front, Layers::Slice),
+            Some('x') => (Right, Layers::Rotation),
+            Some('y') => (Up, Layers::Rotation),
+            Some('z') => (Front, Layers::Rotation),
+            Some(_) => return Err(err(digits, "Move direction isn't valid")),
+            None if digits > 0 => return Err(err(digits, "Layer count needs a wide move")),
+            None => return Err(err(0, "Move format is empty")),
         };
-        if let Some(_) = cs.next() {
-            return Err("Unexpected character in move");
+        let mut layers = layers;
+        if layers == Layers::Outer && cs.peek() == Some(&'w') {
+            cs.next();
+            layers = Layers::Wide(count.unwrap_or(2));
+        } else if count.is_some() {
+            return Err(err(0, "Layer count needs a wide move, e.g. 3Rw"));
         }
+
+        let position = s.chars().count() - cs.clone().count();
+        let modifier = match cs.collect::<String>().as_str() {
+            "" => No,
+            "'" | "’" => Prime,
+            // a half turn is the same either way
+            "2" | "2'" | "2’" => Twice,
+            _ => return Err(err(position, "Move modifier isn't valid")),
+        };
         Ok(Move {
             direction,
             modifier,
+            layers,
         })
     }
 }
 
-#[derive(PartialEq, Clone)]
-enum Direction {
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum Direction {
     Front,
     Back,
     Down,
@@ -107,17 +362,99 @@ enum Direction {
     Left,
 }
 
-#[derive(Clone)]
-enum Modifier {
+impl Direction {
+    pub const ALL: [Direction; 6] = [
+        Direction::Front,
+        Direction::Back,
+        Direction::Down,
+        Direction::Up,
+        Direction::Right,
+        Direction::Left,
+    ];
+
+    /// Opposite faces share an axis, their turns commute
+    pub fn axis(&self) -> usize {
+        *self as usize / 2
+    }
+
+    /// Whether turning `direction` after `moves` doesn't cancel or merge with
+    /// them: no face twice in a row, no three turns on one axis and turns of
+    /// opposite faces only in one order, e.g. `R L` but never `L R`.
+    pub fn can_follow(moves: &[Move], direction: Direction) -> bool {
+        match moves {
+            [.., last] if last.direction.axis() != direction.axis() => true,
+            [.., before, last] if before.direction.axis() == direction.axis() => false,
+            [.., last] => (last.direction as usize) < (direction as usize),
+            [] => true,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum Modifier {
     No,
     Twice,
     Prime,
 }
 
-#[derive(Clone)]
-struct Move {
-    direction: Direction,
-    modifier: Modifier,
+impl Modifier {
+    /// Clockwise quarter turns
+    pub fn quarters(&self) -> usize {
+        match self {
+            Modifier::No => 1,
+            Modifier::Twice => 2,
+            Modifier::Prime => 3,
+        }
+    }
+
+    /// None for a full turn, which is no move at all
+    pub fn from_quarters(quarters: usize) -> Option<Modifier> {
+        match quarters % 4 {
+            1 => Some(Modifier::No),
+            2 => Some(Modifier::Twice),
+            3 => Some(Modifier::Prime),
+            _ => None,
+        }
+    }
+}
+
+/// Layers of the cube a move turns along with its face
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Layers {
+    /// Just the face, e.g. R
+    Outer,
+    /// The face and the layers behind it, 2 for Rw or r, 3 for 3Rw
+    Wide(u8),
+    /// Only the middle layer: M turns like L, E like D and S like F
+    Slice,
+    /// The whole cube: x turns like R, y like U and z like F
+    Rotation,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct Move {
+    pub direction: Direction,
+    pub modifier: Modifier,
+    pub layers: Layers,
+}
+
+impl Move {
+    pub fn inverse(&self) -> Move {
+        Move {
+            modifier: Modifier::from_quarters(4 - self.modifier.quarters()).unwrap(),
+            ..*self
+        }
+    }
+
+    /// Moves it counts as in the half turn metric, a slice turns two outer
+    /// layers and a rotation none
+    pub fn turns(&self) -> usize {
+        match self.layers {
+            Layers::Outer | Layers::Wide(_) => 1,
+            Layers::Slice => 2,
+            Layers::Rotation => 0,
+        }
+    }
 }
 
 // https://stackoverflow.com/questions/48490049
@@ -147,10 +484,11 @@ impl Distribution<Modifier> for Standard {
 }
 
 impl fmt::Display for Move {
+    // fmt: canonical WCA notation, e.g. Rw rather than r and R2 rather than R2'
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use Direction::*;
         use Modifier::*;
-        let letter = match self.direction {
+        let face = match self.direction {
             Front => "F",
             Back => "B",
             Down => "D",
@@ -158,11 +496,126 @@ impl fmt::Display for Move {
             Right => "R",
             Left => "L",
         };
-        let modifier = match self.modifier {
-            No => "",
-            Twice => "2",
-            Prime => "'",
+        // slices and rotations are named after one face of their axis, a
+        // turn like the opposite face is the inverse turn
+        let (letter, inverse) = match (self.layers, self.direction) {
+            (Layers::Slice, Left | Right) => ("M", self.direction == Right),
+            (Layers::Slice, Down | Up) => ("E", self.direction == Up),
+            (Layers::Slice, Front | Back) => ("S", self.direction == Back),
+            (Layers::Rotation, Left | Right) => ("x", self.direction == Left),
+            (Layers::Rotation, Down | Up) => ("y", self.direction == Down),
+            (Layers::Rotation, Front | Back) => ("z", self.direction == Back),
+            _ => (face, false),
+        };
+        let modifier = match (self.modifier, inverse) {
+            (Twice, _) => "2",
+            (No, false) | (Prime, true) => "",
+            (Prime, false) | (No, true) => "'",
         };
-        write!(f, "{}{}", letter, modifier)
+        match self.layers {
+            Layers::Wide(2) => write!(f, "{}w{}", letter, modifier),
+            Layers::Wide(n) => write!(f, "{}{}w{}", n, letter, modifier),
+            _ => write!(f, "{}{}", letter, modifier),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(s: &str) -> Vec<Move> {
+        s.parse::<Scramble>().unwrap().0
+    }
+
+    #[test]
+    fn notation_round_trips() {
+        let scramble: Scramble = "R U' F2 3Rw2 M E' S2 x y' z2".parse().unwrap();
+        assert_eq!(scramble.to_string(), "R U' F2 3Rw2 M E' S2 x y' z2");
+    }
+
+    #[test]
+    fn notation_is_canonical() {
+        let scramble: Scramble = "r Rw' R2' U’ 2Rw".parse().unwrap();
+        assert_eq!(scramble.to_string(), "Rw Rw' R2 U' Rw");
+        // turning like the opposite face is the inverse slice or rotation
+        let m = Move {
+            direction: Direction::Right,
+            modifier: Modifier::No,
+            layers: Layers::Slice,
+        };
+        assert_eq!(m.to_string(), "M'");
+        assert_eq!("x'".parse::<Move>().unwrap().inverse().to_string(), "x");
+    }
+
+    #[test]
+    fn notation_errors() {
+        let err = |s: &str| s.parse::<Scramble>().err().unwrap();
+        assert_eq!(
+            err("R U' Q2"),
+            ParseMoveError::new("Q2", 5, "Move direction isn't valid")
+        );
+        assert_eq!(
+            err("R3"),
+            ParseMoveError::new("R3", 1, "Move modifier isn't valid")
+        );
+        assert_eq!(
+            err("F 3R"),
+            ParseMoveError::new("3R", 2, "Layer count needs a wide move, e.g. 3Rw")
+        );
+        assert_eq!(
+            err("1Rw"),
+            ParseMoveError::new("1Rw", 0, "Wide moves turn 2 or more layers")
+        );
+        assert_eq!(
+            err("3"),
+            ParseMoveError::new("3", 1, "Layer count needs a wide move")
+        );
+        assert_eq!(err("R Mw").position, 3);
+    }
+
+    #[test]
+    fn move_turns() {
+        let turns = |s: &str| moves(s).iter().map(Move::turns).sum::<usize>();
+        assert_eq!(turns("R U2 F'"), 3);
+        assert_eq!(turns("Rw 3Fw2"), 2);
+        assert_eq!(turns("M E' S2"), 6);
+        assert_eq!(turns("x y' z2"), 0);
+    }
+
+    #[test]
+    fn can_follow() {
+        use Direction::*;
+        assert!(Direction::can_follow(&[], Right));
+        assert!(Direction::can_follow(&moves("R"), Up));
+        assert!(!Direction::can_follow(&moves("R"), Right));
+        // opposite faces only in one order
+        assert!(Direction::can_follow(&moves("R"), Left));
+        assert!(!Direction::can_follow(&moves("L"), Right));
+        assert!(!Direction::can_follow(&moves("R L"), Right));
+        assert!(!Direction::can_follow(&moves("U R L"), Left));
+        assert!(Direction::can_follow(&moves("R L"), Up));
+        assert!(Direction::can_follow(&moves("F"), Back));
+    }
+
+    #[test]
+    fn random_scrambles_never_cancel() {
+        seed(1);
+        for scramble in [Scramble::new_rand(20), Scramble::new_fmc(20)] {
+            for i in 1..scramble.0.len() {
+                assert!(Direction::can_follow(
+                    &scramble.0[..i],
+                    scramble.0[i].direction
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn fmc_scrambles_are_padded() {
+        seed(2);
+        let scramble = Scramble::new_fmc(20).to_string();
+        assert!(scramble.starts_with("R' U' F "));
+        assert!(scramble.ends_with(" R' U' F"));
     }
 }
@@ -0,0 +1,199 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str;
+
+use serde_json::Value;
+
+use crate::scramble::{ParseMoveError, Puzzle, Scramble};
+
+/// Named list of scrambles to share, so that everyone solves the same ones
+pub struct ScrambleSet {
+    pub name: String,
+    pub puzzle: Puzzle,
+    pub scrambles: Vec<Scramble>,
+}
+
+#[derive(Debug)]
+pub enum ScrambleSetError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// The scramble `at` a line of a text file or an index of a JSON export
+    /// has an invalid move
+    Invalid {
+        at: String,
+        err: ParseMoveError,
+    },
+    Empty,
+}
+
+impl fmt::Display for ScrambleSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScrambleSetError::Io(err) => write!(f, "Can't read the scramble set: {}", err),
+            ScrambleSetError::Json(err) => write!(f, "Invalid TNoodle JSON: {}", err),
+            ScrambleSetError::Invalid { at, err } => write!(f, "{}: {}", at, err),
+            ScrambleSetError::Empty => write!(f, "The scramble set has no scrambles"),
+        }
+    }
+}
+
+impl From<io::Error> for ScrambleSetError {
+    fn from(err: io::Error) -> Self {
+        ScrambleSetError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ScrambleSetError {
+    fn from(err: serde_json::Error) -> Self {
+        ScrambleSetError::Json(err)
+    }
+}
+
+/// Parses a scramble, pointing out the first move that isn't valid
+fn parse_scramble(at: String, scramble: &str) -> Result<Scramble, ScrambleSetError> {
+    scramble
+        .parse::<Scramble>()
+        .map_err(|err| ScrambleSetError::Invalid { at, err })
+}
+
+/// Puzzle of a WCA event id, e.g. 333oh
+fn event_puzzle(event: &str) -> Option<Puzzle> {
+    match event.get(..3)? {
+        "222" => Some(Puzzle::Cube2),
+        "333" => Some(Puzzle::Cube3),
+        "444" => Some(Puzzle::Cube4),
+        "555" => Some(Puzzle::Cube5),
+        _ => None,
+    }
+}
+
+/// Collects the scrambles of a TNoodle export, either the sheets of older
+/// versions or the scramble sets of a WCIF, leaving out extra scrambles
+fn tnoodle_scrambles(value: &Value, scrambles: &mut Vec<String>, puzzle: &mut Option<Puzzle>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                match (key.as_str(), value) {
+                    ("event" | "id", Value::String(event)) if puzzle.is_none() => {
+                        *puzzle = event_puzzle(event)
+                    }
+                    ("scrambles", Value::Array(list)) if list.iter().all(Value::is_string) => {
+                        // multi-blind attempts list their scrambles on separate lines
+                        scrambles.extend(
+                            list.iter()
+                                .filter_map(Value::as_str)
+                                .flat_map(str::lines)
+                                .map(String::from),
+                        )
+                    }
+                    ("extraScrambles", _) => {}
+                    _ => tnoodle_scrambles(value, scrambles, puzzle),
+                }
+            }
+        }
+        Value::Array(list) => {
+            for value in list {
+                tnoodle_scrambles(value, scrambles, puzzle);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl ScrambleSet {
+    /// `count` scrambles, `length` is only used for the 3x3
+    pub fn generate(name: &str, puzzle: Puzzle, count: usize, length: usize) -> ScrambleSet {
+        ScrambleSet {
+            name: String::from(name),
+            puzzle,
+            scrambles: (0..count).map(|_| puzzle.scramble(length)).collect(),
+        }
+    }
+
+    /// Loads a text file with one scramble per line or a TNoodle JSON export
+    pub fn load(path: &str) -> Result<ScrambleSet, ScrambleSetError> {
+        let text = fs::read_to_string(path)?;
+        let mut set = if text.trim_start().starts_with('{') {
+            ScrambleSet::from_tnoodle(&text)?
+        } else {
+            text.parse::<ScrambleSet>()?
+        };
+        if set.name.is_empty() {
+            set.name = Path::new(path)
+                .file_stem()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+        }
+        Ok(set)
+    }
+
+    pub fn from_tnoodle(json: &str) -> Result<ScrambleSet, ScrambleSetError> {
+        let value: Value = serde_json::from_str(json)?;
+        let mut scrambles = vec![];
+        let mut puzzle = None;
+        tnoodle_scrambles(&value, &mut scrambles, &mut puzzle);
+        if scrambles.is_empty() {
+            return Err(ScrambleSetError::Empty);
+        }
+        let name = ["competitionName", "name"]
+            .iter()
+            .find_map(|key| value.get(key)?.as_str())
+            .unwrap_or_default();
+        Ok(ScrambleSet {
+            name: String::from(name),
+            puzzle: puzzle.unwrap_or(Puzzle::Cube3),
+            scrambles: scrambles
+                .iter()
+                .enumerate()
+                .map(|(i, scramble)| parse_scramble(format!("Scramble {}", i + 1), scramble))
+                .collect::<Result<Vec<Scramble>, ScrambleSetError>>()?,
+        })
+    }
+}
+
+impl fmt::Display for ScrambleSet {
+    // fmt: a comment header, then one scramble per line
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# name: {}", self.name)?;
+        writeln!(f, "# puzzle: {}", self.puzzle)?;
+        for scramble in &self.scrambles {
+            writeln!(f, "{}", scramble)?;
+        }
+        Ok(())
+    }
+}
+
+impl str::FromStr for ScrambleSet {
+    type Err = ScrambleSetError;
+    // fmt: one scramble per line, blank lines and # comments are skipped
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = ScrambleSet {
+            name: String::new(),
+            puzzle: Puzzle::Cube3,
+            scrambles: vec![],
+        };
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix('#') {
+                match comment.trim().split_once(':') {
+                    Some(("name", name)) => set.name = String::from(name.trim()),
+                    Some(("puzzle", puzzle)) => {
+                        if let Ok(puzzle) = puzzle.trim().parse() {
+                            set.puzzle = puzzle
+                        }
+                    }
+                    _ => {}
+                }
+            } else if !line.is_empty() {
+                set.scrambles
+                    .push(parse_scramble(format!("Line {}", i + 1), line)?);
+            }
+        }
+        if set.scrambles.is_empty() {
+            return Err(ScrambleSetError::Empty);
+        }
+        Ok(set)
+    }
+}
@@ -0,0 +1,408 @@
+use std::fs;
+
+use toml_edit::{Array, Document, Item, Value};
+
+use crate::{
+    config::{CubeConfig, DEFAULT_CONFIG},
+    scramble::MAX_MIN_MOVES,
+};
+
+/// How the value of a setting is edited
+pub enum Kind {
+    /// Whole number from `min` to `max`
+    Number {
+        min: i64,
+        max: i64,
+    },
+    /// Whole number from `min` to `max` that can be left out
+    OptionalNumber {
+        min: i64,
+        max: i64,
+    },
+    Toggle,
+    /// One of the names
+    Choice(&'static [&'static str]),
+    /// Any of the names, in order, typed in separated by commas
+    Choices(&'static [&'static str]),
+    /// Text that can be left out
+    Text,
+    /// Any number of texts, typed in separated by commas
+    Texts,
+}
+
+/// A key of the config file as listed in the settings screen
+pub struct Setting {
+    pub section: &'static str,
+    pub key: &'static str,
+    pub kind: Kind,
+    pub help: &'static str,
+}
+
+const PUZZLES: &[&str] = &["2x2", "3x3", "4x4", "5x5"];
+
+/// Every setting of `CubeConfig`, in the order of DEFAULT_CONFIG
+pub const SETTINGS: [Setting; 25] = [
+    Setting {
+        section: "scramble",
+        key: "length",
+        kind: Kind::Number { min: 1, max: 100 },
+        help: "Moves per 3x3 scramble",
+    },
+    Setting {
+        section: "scramble",
+        key: "seed",
+        kind: Kind::OptionalNumber {
+            min: 0,
+            max: i64::MAX,
+        },
+        help: "Same scrambles in the same order for everyone using this seed",
+    },
+    Setting {
+        section: "scramble",
+        key: "file",
+        kind: Kind::Text,
+        help: "Scramble set served in order, one per line or a TNoodle JSON export",
+    },
+    Setting {
+        section: "scramble",
+        key: "min_moves",
+        kind: Kind::Number {
+            min: 0,
+            max: MAX_MIN_MOVES as i64,
+        },
+        help: "Reject scrambles solvable in fewer moves, at most 6",
+    },
+    Setting {
+        section: "inspection",
+        key: "length",
+        kind: Kind::Number { min: 0, max: 60 },
+        help: "Seconds of inspection",
+    },
+    Setting {
+        section: "inspection",
+        key: "key_hold",
+        kind: Kind::Number { min: 0, max: 10 },
+        help: "Seconds <Space> is held before the timer starts",
+    },
+    Setting {
+        section: "session",
+        key: "mode",
+        kind: Kind::Choice(&[
+            "normal", "bld", "multibld", "fmc", "relay", "compsim", "trainer", "plan",
+        ]),
+        help: "Kind of solves timed",
+    },
+    Setting {
+        section: "session",
+        key: "phases",
+        kind: Kind::Number { min: 1, max: 10 },
+        help: "Phases each solve is split into, any key records a split",
+    },
+    Setting {
+        section: "session",
+        key: "phase_names",
+        kind: Kind::Texts,
+        help: "Names of the phases, e.g. Cross, F2L, LL",
+    },
+    Setting {
+        section: "session",
+        key: "hide_timer",
+        kind: Kind::Toggle,
+        help: "Hide the running time while solving",
+    },
+    Setting {
+        section: "session",
+        key: "relay",
+        kind: Kind::Choices(PUZZLES),
+        help: "Puzzles of a relay in the order they are solved",
+    },
+    Setting {
+        section: "round",
+        key: "format",
+        kind: Kind::Choice(&["ao5", "mo3", "bo3"]),
+        help: "How the result of a competition simulation round is computed",
+    },
+    Setting {
+        section: "round",
+        key: "time_limit",
+        kind: Kind::OptionalNumber { min: 1, max: 3600 },
+        help: "Seconds per solve before it is a DNF",
+    },
+    Setting {
+        section: "round",
+        key: "cumulative_limit",
+        kind: Kind::OptionalNumber { min: 1, max: 3600 },
+        help: "Seconds for every solve of the round together",
+    },
+    Setting {
+        section: "round",
+        key: "cutoff",
+        kind: Kind::OptionalNumber { min: 1, max: 3600 },
+        help: "Seconds one of the first cutoff_solves solves has to beat",
+    },
+    Setting {
+        section: "round",
+        key: "cutoff_solves",
+        kind: Kind::Number { min: 1, max: 5 },
+        help: "Solves that can make the cutoff",
+    },
+    Setting {
+        section: "trainer",
+        key: "sets",
+        kind: Kind::Choices(&["oll", "pll", "coll", "zbll"]),
+        help: "Algorithm sets the trainer sets up cases of",
+    },
+    Setting {
+        section: "plan",
+        key: "goal",
+        kind: Kind::Choice(&["cross", "eocross", "firstblock", "2x2x2"]),
+        help: "Step planned during inspection",
+    },
+    Setting {
+        section: "plan",
+        key: "solutions",
+        kind: Kind::Number { min: 1, max: 20 },
+        help: "Optimal solutions shown next to the planned one",
+    },
+    Setting {
+        section: "library",
+        key: "target_time",
+        kind: Kind::Number { min: 1, max: 60 },
+        help: "Seconds a drill may take before its recall counts as one step worse",
+    },
+    Setting {
+        section: "stackmat",
+        key: "source",
+        kind: Kind::Text,
+        help: "Serial device or PCM recording / pipe of a Stackmat, on restart",
+    },
+    Setting {
+        section: "stackmat",
+        key: "format",
+        kind: Kind::Choice(&["serial", "pcm"]),
+        help: "How the Stackmat source is read, on restart",
+    },
+    Setting {
+        section: "stackmat",
+        key: "sample_rate",
+        kind: Kind::Number {
+            min: 8000,
+            max: 192000,
+        },
+        help: "Sample rate of a PCM Stackmat source, on restart",
+    },
+    Setting {
+        section: "smartcube",
+        key: "source",
+        kind: Kind::Text,
+        help: "File, pipe or tcp://host:port of a smart cube bridge, on restart",
+    },
+    Setting {
+        section: "smartcube",
+        key: "protocol",
+        kind: Kind::Choice(&["text", "gan", "giiker", "moyu"]),
+        help: "What the smart cube bridge sends, on restart",
+    },
+];
+
+fn list(input: &str) -> Vec<&str> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+impl Kind {
+    /// What can be typed in, e.g. for the prompt
+    pub fn hint(&self) -> String {
+        match self {
+            Kind::Number { min, max } => format!("A number from {} to {}", min, max),
+            Kind::OptionalNumber { min, max } => {
+                format!(
+                    "A number from {} to {}, x in the list leaves it out",
+                    min, max
+                )
+            }
+            Kind::Toggle => String::from("true or false"),
+            Kind::Choice(names) => format!("One of {}", names.join(", ")),
+            Kind::Choices(names) => format!("Any of {}, separated by commas", names.join(", ")),
+            Kind::Text => String::from("Any text, x in the list leaves it out"),
+            Kind::Texts => String::from("Any texts, separated by commas"),
+        }
+    }
+
+    /// Checks the typed in `input` and turns it into a value of the file
+    pub fn parse(&self, input: &str) -> Result<Value, String> {
+        let input = input.trim();
+        match self {
+            Kind::Number { min, max } | Kind::OptionalNumber { min, max } => input
+                .parse::<i64>()
+                .ok()
+                .filter(|n| (min..=max).contains(&n))
+                .map(Value::from)
+                .ok_or_else(|| format!("'{}' isn't a number from {} to {}", input, min, max)),
+            Kind::Toggle => match input {
+                "true" | "on" | "yes" => Ok(Value::from(true)),
+                "false" | "off" | "no" => Ok(Value::from(false)),
+                _ => Err(format!("'{}' isn't true or false", input)),
+            },
+            Kind::Choice(names) => names
+                .iter()
+                .find(|&&name| name == input.to_lowercase())
+                .map(|&name| Value::from(name))
+                .ok_or_else(|| format!("'{}' isn't one of {}", input, names.join(", "))),
+            Kind::Choices(names) => {
+                let mut values = Array::new();
+                for choice in list(input) {
+                    let name = names
+                        .iter()
+                        .find(|&&name| name == choice.to_lowercase())
+                        .ok_or_else(|| format!("'{}' isn't one of {}", choice, names.join(", ")))?;
+                    values.push(*name);
+                }
+                Ok(Value::Array(values))
+            }
+            Kind::Text if input.is_empty() => {
+                Err(String::from("Press x in the list to leave it out"))
+            }
+            Kind::Text => Ok(Value::from(input)),
+            Kind::Texts => Ok(Value::Array(list(input).into_iter().collect())),
+        }
+    }
+
+    fn optional(&self) -> bool {
+        matches!(self, Kind::OptionalNumber { .. } | Kind::Text)
+    }
+}
+
+impl Setting {
+    pub fn name(&self) -> String {
+        format!("{}.{}", self.section, self.key)
+    }
+}
+
+/// Value as it is typed in
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.value().clone(),
+        Value::Integer(n) => n.value().to_string(),
+        Value::Boolean(b) => b.value().to_string(),
+        Value::Array(values) => values.iter().map(display).collect::<Vec<_>>().join(", "),
+        value => value.to_string().trim().to_string(),
+    }
+}
+
+/// The config file in use, edited in place so that its comments, layout and
+/// any keys unknown to the settings screen stay as they are
+pub struct Settings {
+    path: String,
+    document: Document,
+    defaults: Document,
+}
+
+impl Settings {
+    pub fn load() -> Result<Settings, String> {
+        let path = CubeConfig::get_config_path().ok_or("No config file")?;
+        let file =
+            fs::read_to_string(&path).map_err(|err| format!("Can't read {}: {}", path, err))?;
+        let document = file
+            .parse::<Document>()
+            .map_err(|err| format!("Invalid config {}: {}", path, err))?;
+        Ok(Settings {
+            path,
+            document,
+            defaults: DEFAULT_CONFIG.parse().unwrap(),
+        })
+    }
+
+    fn value<'a>(document: &'a Document, setting: &Setting) -> Option<&'a Value> {
+        document
+            .get(setting.section)
+            .and_then(|section| section.get(setting.key))
+            .and_then(Item::as_value)
+    }
+
+    /// Value of `setting` in the file, or its default, as it is typed in
+    pub fn get(&self, setting: &Setting) -> Option<String> {
+        Settings::value(&self.document, setting)
+            .or_else(|| Settings::value(&self.defaults, setting))
+            .map(display)
+    }
+
+    /// Sets `setting` to the typed in `input`
+    pub fn set(&mut self, setting: &Setting, input: &str) -> Result<(), String> {
+        let value = setting.kind.parse(input)?;
+        self.write(setting, Some(value))
+    }
+
+    /// Adds `delta` to a number, picks a choice `delta` further along or
+    /// flips a toggle
+    pub fn step(&mut self, setting: &Setting, delta: i64) -> Result<(), String> {
+        let current = self.get(setting).unwrap_or_default();
+        let value = match setting.kind {
+            Kind::Number { min, max } | Kind::OptionalNumber { min, max } => {
+                let n = current
+                    .parse::<i64>()
+                    .map_or(min, |n| n.saturating_add(delta));
+                Value::from(n.clamp(min, max))
+            }
+            Kind::Toggle => Value::from(current != "true"),
+            Kind::Choice(names) => {
+                let i = names.iter().position(|&name| name == current).unwrap_or(0) as i64;
+                Value::from(names[(i + delta).rem_euclid(names.len() as i64) as usize])
+            }
+            _ => return Err(String::from("Press <Enter> to type it in")),
+        };
+        self.write(setting, Some(value))
+    }
+
+    /// Leaves `setting` out of the file, when it can be
+    pub fn unset(&mut self, setting: &Setting) -> Result<(), String> {
+        if !setting.kind.optional() {
+            return Err(format!("{} can't be left out", setting.name()));
+        }
+        self.write(setting, None)
+    }
+
+    /// Writes `value` to the file, once the whole config is known to be valid
+    fn write(&mut self, setting: &Setting, value: Option<Value>) -> Result<(), String> {
+        let mut document = self.document.clone();
+        match value {
+            Some(mut value) => {
+                let section = document
+                    .entry(setting.section)
+                    .or_insert(toml_edit::table())
+                    .as_table_mut()
+                    .ok_or_else(|| format!("[{}] isn't a table", setting.section))?;
+                // keeps the comments around the old value
+                match section.get_mut(setting.key) {
+                    Some(Item::Value(old)) => {
+                        *value.decor_mut() = old.decor().clone();
+                        *old = value;
+                    }
+                    _ => {
+                        section.insert(setting.key, Item::Value(value));
+                    }
+                }
+            }
+            // an input without a source is left out altogether
+            None if setting.key == "source" => {
+                document.remove(setting.section);
+            }
+            None => {
+                if let Some(section) = document
+                    .get_mut(setting.section)
+                    .and_then(Item::as_table_mut)
+                {
+                    section.remove(setting.key);
+                }
+            }
+        }
+        let file = document.to_string();
+        CubeConfig::parse(&self.path, &file).map_err(|err| err.to_string())?;
+        fs::write(&self.path, file).map_err(|err| format!("Can't write {}: {}", self.path, err))?;
+        self.document = document;
+        Ok(())
+    }
+}
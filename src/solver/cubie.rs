@@ -0,0 +1,145 @@
+//! The cube as pieces in slots, which is what the two-phase solver works on
+
+use std::sync::OnceLock;
+
+use crate::cube::{self, Cube};
+
+use super::Turn;
+
+/// Stickers of each slot, starting with the one on U / D, or on F / B for the
+/// edges of the middle layer. Its index is the orientation of the piece in it.
+pub struct Slots {
+    /// Corners of the U layer then of the D layer
+    pub corners: Vec<Vec<usize>>,
+    /// Edges of the U and D layers then of the middle layer, the last 4
+    pub edges: Vec<Vec<usize>>,
+}
+
+pub fn slots() -> &'static Slots {
+    static SLOTS: OnceLock<Slots> = OnceLock::new();
+    SLOTS.get_or_init(|| {
+        let slots = |kind: usize, first: fn(&[i8; 3]) -> bool, reference: [usize; 2]| {
+            let mut slots: Vec<(bool, Vec<usize>)> = cube::pieces()
+                .iter()
+                .filter(|(_, stickers)| stickers.len() == kind)
+                .map(|(pos, stickers)| {
+                    let faces = if pos[1] == 0 { [2, 5] } else { reference };
+                    let start = stickers
+                        .iter()
+                        .position(|&s| faces.contains(&(s / 9)))
+                        .unwrap();
+                    let mut stickers = stickers.clone();
+                    stickers.rotate_left(start);
+                    (!first(pos), stickers)
+                })
+                .collect();
+            slots.sort_by_key(|(last, _)| *last);
+            slots.into_iter().map(|(_, stickers)| stickers).collect()
+        };
+        Slots {
+            corners: slots(3, |pos| pos[1] == 1, [0, 3]),
+            edges: slots(2, |pos| pos[1] != 0, [0, 3]),
+        }
+    })
+}
+
+/// Pieces by slot, each piece numbered after its solved slot, and how they
+/// are twisted or flipped
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CubieCube {
+    pub cp: [u8; 8],
+    pub co: [u8; 8],
+    pub ep: [u8; 12],
+    pub eo: [u8; 12],
+}
+
+impl Default for CubieCube {
+    fn default() -> Self {
+        CubieCube {
+            cp: std::array::from_fn(|i| i as u8),
+            co: [0; 8],
+            ep: std::array::from_fn(|i| i as u8),
+            eo: [0; 12],
+        }
+    }
+}
+
+/// Where a move takes the piece of each slot in each orientation
+pub struct CubieMove {
+    corners: [[(u8, u8); 3]; 8],
+    edges: [[(u8, u8); 2]; 12],
+}
+
+/// Slot and orientation of each sticker location of the given slots
+fn locations(slots: &[Vec<usize>]) -> [(u8, u8); 54] {
+    let mut locations = [(0, 0); 54];
+    for (slot, stickers) in slots.iter().enumerate() {
+        for (o, &s) in stickers.iter().enumerate() {
+            locations[s] = (slot as u8, o as u8);
+        }
+    }
+    locations
+}
+
+impl CubieMove {
+    /// The move of a turn that keeps the centers in place, e.g. a face turn
+    pub fn new((_, moves): &Turn) -> Self {
+        let Slots { corners, edges } = slots();
+        let (corner_locations, edge_locations) = (locations(corners), locations(edges));
+        CubieMove {
+            corners: std::array::from_fn(|slot| {
+                std::array::from_fn(|o| corner_locations[moves[corners[slot][o]] as usize])
+            }),
+            edges: std::array::from_fn(|slot| {
+                std::array::from_fn(|o| edge_locations[moves[edges[slot][o]] as usize])
+            }),
+        }
+    }
+}
+
+impl From<&Cube> for CubieCube {
+    /// Pieces of `cube` relative to its centers
+    fn from(cube: &Cube) -> Self {
+        let facelets = cube.facelets();
+        let Slots { corners, edges } = slots();
+        // the piece whose faces are on the stickers of `slot`, and which of
+        // them has its reference face
+        let piece = |slots: &[Vec<usize>], slot: usize| -> (u8, u8) {
+            let faces: Vec<u8> = slots[slot].iter().map(|&s| facelets[s]).collect();
+            let home = slots
+                .iter()
+                .position(|stickers| {
+                    stickers.len() == faces.len()
+                        && stickers.iter().all(|&s| faces.contains(&((s / 9) as u8)))
+                })
+                .unwrap_or(slot);
+            let reference = (slots[home][0] / 9) as u8;
+            let o = faces.iter().position(|&f| f == reference).unwrap_or(0);
+            (home as u8, o as u8)
+        };
+        let mut cubie = CubieCube::default();
+        for slot in 0..8 {
+            (cubie.cp[slot], cubie.co[slot]) = piece(corners, slot);
+        }
+        for slot in 0..12 {
+            (cubie.ep[slot], cubie.eo[slot]) = piece(edges, slot);
+        }
+        cubie
+    }
+}
+
+impl CubieCube {
+    pub fn apply(&mut self, m: &CubieMove) {
+        let old = self.clone();
+        for slot in 0..8 {
+            let (to, o) = m.corners[slot][old.co[slot] as usize];
+            self.cp[to as usize] = old.cp[slot];
+            self.co[to as usize] = o;
+        }
+        for slot in 0..12 {
+            let (to, o) = m.edges[slot][old.eo[slot] as usize];
+            self.ep[to as usize] = old.ep[slot];
+            self.eo[to as usize] = o;
+        }
+    }
+}
@@ -0,0 +1,315 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::{
+    cube::{self, Cube},
+    scramble::{Direction, Layers, Modifier, Move, Scramble},
+};
+
+use cubie::CubieCube;
+use steps::{Method, Step};
+
+pub mod cubie;
+pub mod steps;
+pub mod two_phase;
+
+/// Time spent looking for a shorter solution
+pub const SOLVE_TIME: Duration = Duration::from_secs(1);
+
+/// A short solution of a scramble, and how CFOP and Roux solvers would go
+/// about it step by step
+pub struct Solution {
+    pub moves: Scramble,
+    pub steps: Vec<(Method, Vec<Step>)>,
+}
+
+/// Solves `scramble`, looking for a shorter solution for up to `time`
+pub fn solve(scramble: &Scramble, time: Duration) -> Solution {
+    let cube = Cube::scrambled(scramble);
+    Solution {
+        moves: Scramble::from(two_phase::solve(&CubieCube::from(&cube), time)),
+        steps: Method::ALL
+            .into_iter()
+            .map(|method| (method, steps::steps(method, &cube)))
+            .collect(),
+    }
+}
+
+/// A move and where it takes every sticker
+pub type Turn = (Move, [u8; 54]);
+
+/// Quarter, half and counter quarter turn of the given layers
+pub fn turns_of(direction: Direction, layers: Layers) -> impl Iterator<Item = Move> {
+    [Modifier::No, Modifier::Twice, Modifier::Prime]
+        .into_iter()
+        .map(move |modifier| Move {
+            direction,
+            modifier,
+            layers,
+        })
+}
+
+/// Every face turn of the 3x3
+fn moves() -> impl Iterator<Item = Move> {
+    Direction::ALL
+        .into_iter()
+        .flat_map(|direction| turns_of(direction, Layers::Outer))
+}
+
+fn dfs(cube: &Cube, depth: usize, path: &mut Vec<Move>, is_goal: &impl Fn(&Cube) -> bool) -> bool {
+    if is_goal(cube) {
+        return true;
+    }
+    if depth == 0 {
+        return false;
+    }
+    for m in moves() {
+        if !Direction::can_follow(path, m.direction) {
+            continue;
+        }
+        let mut next = cube.clone();
+        next.apply(&m);
+        path.push(m);
+        if dfs(&next, depth - 1, path, is_goal) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+/// Shortest sequence of at most `max_depth` face turns that takes `cube` to a
+/// state satisfying `is_goal`, found by iterative deepening
+pub fn search(cube: &Cube, max_depth: usize, is_goal: impl Fn(&Cube) -> bool) -> Option<Vec<Move>> {
+    let mut path = vec![];
+    (0..=max_depth)
+        .find(|&depth| dfs(cube, depth, &mut path, &is_goal))
+        .map(|_| path)
+}
+
+/// Where each of `moves` takes every sticker, to search with other moves
+/// than the face turns
+pub fn turn_set(moves: impl IntoIterator<Item = Move>) -> Vec<Turn> {
+    moves
+        .into_iter()
+        .map(|m| (m, cube::sticker_moves(&m)))
+        .collect()
+}
+
+/// Where each face turn takes every sticker
+pub fn turns() -> &'static [Turn] {
+    static TURNS: OnceLock<Vec<Turn>> = OnceLock::new();
+    TURNS.get_or_init(|| turn_set(moves()))
+}
+
+fn turn(stickers: &[u8], moves: &[u8; 54]) -> Vec<u8> {
+    stickers.iter().map(|&s| moves[s as usize]).collect()
+}
+
+/// Follows one sticker of each piece of interest through `moves`, which is
+/// enough to know where the pieces are and how they are twisted
+pub fn follow(stickers: &[u8], moves: &[Move]) -> Vec<u8> {
+    moves.iter().fold(stickers.to_vec(), |stickers, m| {
+        turn(&stickers, &cube::sticker_moves(m))
+    })
+}
+
+/// Where the followed stickers are on `cube` going by its centers, whichever
+/// way it is held. Centers stay where they are.
+pub fn locate(cube: &Cube, stickers: &[u8]) -> Vec<u8> {
+    let facelets = cube.facelets();
+    stickers
+        .iter()
+        .map(|&home| {
+            let home = home as usize;
+            let piece = cube::pieces()
+                .iter()
+                .find(|(_, stickers)| stickers.contains(&home));
+            let faces: Vec<u8> = match piece {
+                Some((_, stickers)) => stickers.iter().map(|&s| (s / 9) as u8).collect(),
+                None => return home as u8,
+            };
+            let (_, slot) = cube::pieces()
+                .iter()
+                .find(|(_, slot)| {
+                    slot.len() == faces.len() && slot.iter().all(|&s| faces.contains(&facelets[s]))
+                })
+                .unwrap();
+            let at = slot.iter().find(|&&s| facelets[s] as usize == home / 9);
+            *at.unwrap() as u8
+        })
+        .collect()
+}
+
+/// Rank of each sticker among the stickers of its kind: 0..24 for edges and
+/// corners, the face for centers
+fn ranks() -> &'static [usize; 54] {
+    static RANKS: OnceLock<[usize; 54]> = OnceLock::new();
+    RANKS.get_or_init(|| {
+        let mut ranks: [usize; 54] = std::array::from_fn(|s| s / 9);
+        for kind in [2, 3] {
+            let stickers = cube::pieces()
+                .iter()
+                .filter(|(_, stickers)| stickers.len() == kind)
+                .flat_map(|(_, stickers)| stickers);
+            for (rank, &s) in stickers.enumerate() {
+                ranks[s] = rank;
+            }
+        }
+        ranks
+    })
+}
+
+/// Entries of a table indexed by `pattern_index` of `stickers` stickers
+pub fn pattern_size(stickers: usize) -> usize {
+    24usize.pow(stickers as u32)
+}
+
+/// Index of followed stickers in a table of `pattern_size` entries
+pub fn pattern_index(stickers: &[u8]) -> usize {
+    stickers
+        .iter()
+        .fold(0, |index, &s| index * 24 + ranks()[s as usize])
+}
+
+/// Number of `turns` needed to get each `key` of followed stickers back to
+/// the key of `solved`, found by a breadth first search from `solved`.
+/// Keys that can't be reached are `u8::MAX`.
+pub fn pattern_table(
+    solved: &[u8],
+    turns: &[Turn],
+    size: usize,
+    key: impl Fn(&[u8]) -> usize,
+) -> Vec<u8> {
+    let mut table = vec![u8::MAX; size];
+    table[key(solved)] = 0;
+    let mut frontier = vec![solved.to_vec()];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = vec![];
+        for stickers in &frontier {
+            for (_, moves) in turns {
+                let stickers = turn(stickers, moves);
+                let key = key(&stickers);
+                if table[key] == u8::MAX {
+                    table[key] = depth;
+                    next.push(stickers);
+                }
+            }
+        }
+        frontier = next;
+    }
+    table
+}
+
+/// Edge slot of each sticker location, 0..12
+fn edge_slots() -> &'static [usize; 54] {
+    static SLOTS: OnceLock<[usize; 54]> = OnceLock::new();
+    SLOTS.get_or_init(|| {
+        let mut slots = [0; 54];
+        let edges = cube::pieces().iter().filter(|(_, s)| s.len() == 2);
+        for (slot, (_, stickers)) in edges.enumerate() {
+            for &s in stickers {
+                slots[s] = slot;
+            }
+        }
+        slots
+    })
+}
+
+/// Locations where the edge whose followed sticker starts at each index is
+/// oriented: the ones reached without F or B quarter turns
+fn oriented() -> &'static Vec<[bool; 54]> {
+    static ORIENTED: OnceLock<Vec<[bool; 54]>> = OnceLock::new();
+    ORIENTED.get_or_init(|| {
+        let keeping: Vec<&[u8; 54]> = turns()
+            .iter()
+            .filter(|(m, _)| {
+                !matches!(m.direction, Direction::Front | Direction::Back)
+                    || m.modifier == Modifier::Twice
+            })
+            .map(|(_, moves)| moves)
+            .collect();
+        (0..54)
+            .map(|start| {
+                let mut reached = [false; 54];
+                reached[start] = true;
+                let mut frontier = vec![start];
+                while let Some(s) = frontier.pop() {
+                    for moves in &keeping {
+                        let next = moves[s] as usize;
+                        if !reached[next] {
+                            reached[next] = true;
+                            frontier.push(next);
+                        }
+                    }
+                }
+                reached
+            })
+            .collect()
+    })
+}
+
+/// Key of the edges flipped for the front / back axis, one bit per edge slot,
+/// from the followed stickers of every edge starting at `solved`
+pub fn edge_orientation(solved: &[u8], stickers: &[u8]) -> usize {
+    solved
+        .iter()
+        .zip(stickers)
+        .filter(|&(&start, &s)| !oriented()[start as usize][s as usize])
+        .fold(0, |key, (_, &s)| key | 1 << edge_slots()[s as usize])
+}
+
+/// IDA* collecting every solution of the depth being searched
+struct Search<'a, F> {
+    turns: &'a [Turn],
+    estimate: F,
+    solutions: Vec<Vec<Move>>,
+    max_solutions: usize,
+}
+
+impl<F: Fn(&[u8]) -> usize> Search<'_, F> {
+    fn dfs(&mut self, stickers: &[u8], depth: usize, path: &mut Vec<Move>) {
+        let estimate = (self.estimate)(stickers);
+        if depth == 0 && estimate == 0 {
+            self.solutions.push(path.clone());
+        }
+        if estimate > depth || depth == 0 || self.solutions.len() >= self.max_solutions {
+            return;
+        }
+        for (m, moves) in self.turns {
+            if !Direction::can_follow(path, m.direction) {
+                continue;
+            }
+            path.push(*m);
+            self.dfs(&turn(stickers, moves), depth - 1, path);
+            path.pop();
+        }
+    }
+}
+
+/// Up to `max_solutions` of the shortest sequences of `turns` solving the
+/// followed stickers, found by IDA*. `estimate` never overestimates the moves
+/// left and is 0 only once solved.
+pub fn optimal(
+    stickers: &[u8],
+    turns: &[Turn],
+    max_depth: usize,
+    max_solutions: usize,
+    estimate: impl Fn(&[u8]) -> usize,
+) -> Vec<Vec<Move>> {
+    let mut search = Search {
+        turns,
+        estimate,
+        solutions: vec![],
+        max_solutions,
+    };
+    for depth in 0..=max_depth {
+        search.dfs(stickers, depth, &mut vec![]);
+        if !search.solutions.is_empty() {
+            break;
+        }
+    }
+    search.solutions
+}
@@ -0,0 +1,386 @@
+//! Step by step solutions the way CFOP and Roux solvers go about a scramble.
+//! Blocks and F2L pairs are searched for, last layer steps are looked up
+//! among the trainer's algorithms.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::{
+    cube::{self, Cube, Vec3},
+    plan::Goal,
+    scramble::{Direction, Layers, Scramble},
+    trainer::{auf, AlgSet},
+};
+
+use super::{
+    follow, locate, optimal, pattern_index, pattern_size, pattern_table, turn, turn_set, turns,
+    turns_of, Turn,
+};
+
+const MAX_DEPTH: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Cfop,
+    Roux,
+}
+
+impl Method {
+    pub const ALL: [Method; 2] = [Method::Cfop, Method::Roux];
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Method::Cfop => "CFOP",
+            Method::Roux => "Roux",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub struct Step {
+    pub name: String,
+    pub moves: Scramble,
+}
+
+/// Followed sticker of every piece in `cube::pieces()` order, then the
+/// centers
+fn home() -> Vec<u8> {
+    cube::pieces()
+        .iter()
+        .map(|(_, stickers)| stickers[0] as u8)
+        .chain((0..6).map(|face| face * 9 + 4))
+        .collect()
+}
+
+/// Index in `home()` of the piece solved at `pos`
+fn piece(pos: Vec3) -> usize {
+    cube::pieces().iter().position(|(p, _)| *p == pos).unwrap()
+}
+
+/// Indices in `home()` of the pieces solved at a position `in_block`
+fn pieces(in_block: impl Fn(Vec3) -> bool) -> Vec<usize> {
+    (0..cube::pieces().len())
+        .filter(|&i| in_block(cube::pieces()[i].0))
+        .collect()
+}
+
+/// The cube being solved, by its followed stickers
+struct Solve {
+    stickers: Vec<u8>,
+    home: Vec<u8>,
+    steps: Vec<Step>,
+}
+
+impl Solve {
+    fn new(cube: &Cube) -> Self {
+        let home = home();
+        Solve {
+            stickers: locate(cube, &home),
+            home,
+            steps: vec![],
+        }
+    }
+
+    /// Adds a step, leaving out the ones that were already solved
+    fn push(&mut self, name: String, moves: Scramble) {
+        if moves.moves().is_empty() {
+            return;
+        }
+        self.stickers = follow(&self.stickers, moves.moves());
+        self.steps.push(Step { name, moves });
+    }
+
+    /// The first of `algs` that makes `done` true after the followed
+    /// stickers, with the top layer adjusted before and after
+    fn find_alg<'a>(
+        &self,
+        algs: impl IntoIterator<Item = (String, &'a Scramble)>,
+        done: impl Fn(&[u8]) -> bool,
+    ) -> Option<(String, Scramble)> {
+        algs.into_iter().find_map(|(name, alg)| {
+            (0..4)
+                .flat_map(|pre| (0..4).map(move |post| auf(pre).then(alg).then(&auf(post))))
+                .find(|moves| done(&follow(&self.stickers, moves.moves())))
+                .map(|moves| (name.clone(), moves))
+        })
+    }
+
+    /// Shortest sequence of `turns` solving the pieces `indices`, `estimate`
+    /// being given their followed stickers
+    fn search(
+        &self,
+        indices: &[usize],
+        turns: &[Turn],
+        estimate: impl Fn(&[u8]) -> usize,
+    ) -> Option<Scramble> {
+        let stickers: Vec<u8> = indices.iter().map(|&i| self.stickers[i]).collect();
+        optimal(&stickers, turns, MAX_DEPTH, 1, estimate)
+            .pop()
+            .map(Scramble::from)
+    }
+}
+
+/// Pieces of each F2L slot with its two cross edges, and the moves needed to
+/// solve them
+struct F2l {
+    cross: Vec<usize>,
+    cross_table: Vec<u8>,
+    slots: Vec<(String, Vec<usize>, Vec<u8>)>,
+}
+
+fn f2l() -> &'static F2l {
+    static F2L: OnceLock<F2l> = OnceLock::new();
+    F2L.get_or_init(|| {
+        let home = home();
+        let table = |pieces: &[usize]| {
+            let stickers: Vec<u8> = pieces.iter().map(|&i| home[i]).collect();
+            let size = pattern_size(stickers.len());
+            pattern_table(&stickers, turns(), size, pattern_index)
+        };
+        let cross = pieces(|[x, y, z]| y == -1 && (x == 0 || z == 0));
+        let slots = [(1, "FR", 1), (1, "FL", -1), (-1, "BL", -1), (-1, "BR", 1)]
+            .into_iter()
+            .map(|(z, name, x)| {
+                let pieces = vec![
+                    piece([x, -1, z]),
+                    piece([x, 0, z]),
+                    piece([x, -1, 0]),
+                    piece([0, -1, z]),
+                ];
+                let table = table(&pieces);
+                (String::from(name), pieces, table)
+            })
+            .collect();
+        F2l {
+            cross_table: table(&cross),
+            cross,
+            slots,
+        }
+    })
+}
+
+fn cfop(solve: &mut Solve, cube: &Cube) {
+    if let Some(cross) = Goal::Cross.solutions(cube, 1).pop() {
+        solve.push(String::from("Cross"), cross);
+    }
+
+    // one pair after the other, whichever is the quickest to solve next
+    let F2l {
+        cross,
+        cross_table,
+        slots,
+    } = f2l();
+    let lookup = |table: &[u8], stickers: &[u8], pieces: &[usize]| {
+        let stickers: Vec<u8> = pieces.iter().map(|&i| stickers[i]).collect();
+        table[pattern_index(&stickers)] as usize
+    };
+    let mut paired = [false; 4];
+    while paired.contains(&false) {
+        let estimate = |stickers: &[u8]| {
+            let next = slots
+                .iter()
+                .zip(paired)
+                .filter(|(_, paired)| !paired)
+                .map(|((_, pieces, table), _)| lookup(table, stickers, pieces))
+                .min()
+                .unwrap_or(0);
+            slots
+                .iter()
+                .zip(paired)
+                .filter(|(_, paired)| *paired)
+                .map(|((_, pieces, table), _)| lookup(table, stickers, pieces))
+                .chain([lookup(cross_table, stickers, cross), next])
+                .max()
+                .unwrap_or(0)
+        };
+        let all: Vec<usize> = (0..solve.stickers.len()).collect();
+        let moves = match solve.search(&all, turns(), estimate) {
+            Some(moves) => moves,
+            None => return,
+        };
+        let stickers = follow(&solve.stickers, moves.moves());
+        let slot = (0..4).find(|&k| !paired[k] && lookup(&slots[k].2, &stickers, &slots[k].1) == 0);
+        if let Some(k) = slot {
+            paired[k] = true;
+            solve.push(format!("F2L {}", slots[k].0), moves);
+        }
+    }
+
+    let last_layer = pieces(|[_, y, _]| y == 1);
+    let oriented = |stickers: &[u8]| last_layer.iter().all(|&i| stickers[i] < 9);
+    if !oriented(&solve.stickers) {
+        let algs = AlgSet::Oll
+            .cases()
+            .iter()
+            .map(|case| (case.id(), &case.setup));
+        let inverses: Vec<(String, Scramble)> =
+            algs.map(|(name, setup)| (name, setup.inverse())).collect();
+        let found = solve.find_alg(inverses.iter().map(|(n, a)| (n.clone(), a)), oriented);
+        if let Some((name, moves)) = found {
+            solve.push(name, moves);
+        }
+    }
+
+    let home = solve.home.clone();
+    let solved = |stickers: &[u8]| last_layer.iter().all(|&i| stickers[i] == home[i]);
+    if !solved(&solve.stickers) {
+        let auf_only = Scramble::default();
+        let inverses: Vec<(String, Scramble)> = [(String::from("AUF"), auf_only)]
+            .into_iter()
+            .chain(
+                AlgSet::Pll
+                    .cases()
+                    .iter()
+                    .map(|case| (case.id(), case.setup.inverse())),
+            )
+            .collect();
+        let found = solve.find_alg(inverses.iter().map(|(n, a)| (n.clone(), a)), solved);
+        if let Some((name, moves)) = found {
+            solve.push(name, moves);
+        }
+    }
+}
+
+/// Moves of the right block once the left one is built, which leave it alone
+fn block_turns() -> &'static [Turn] {
+    static TURNS: OnceLock<Vec<Turn>> = OnceLock::new();
+    TURNS.get_or_init(|| {
+        turn_set(
+            [
+                (Direction::Up, Layers::Outer),
+                (Direction::Right, Layers::Outer),
+                (Direction::Right, Layers::Wide(2)),
+                (Direction::Left, Layers::Slice),
+            ]
+            .into_iter()
+            .flat_map(|(direction, layers)| turns_of(direction, layers)),
+        )
+    })
+}
+
+/// Moves of the last six edges, which leave both blocks and the corners alone
+fn lse_turns() -> &'static [Turn] {
+    static TURNS: OnceLock<Vec<Turn>> = OnceLock::new();
+    TURNS.get_or_init(|| {
+        turn_set(
+            turns_of(Direction::Up, Layers::Outer).chain(turns_of(Direction::Left, Layers::Slice)),
+        )
+    })
+}
+
+/// Second block pieces and moves needed to solve them
+fn second_block() -> &'static (Vec<usize>, Vec<u8>) {
+    static BLOCK: OnceLock<(Vec<usize>, Vec<u8>)> = OnceLock::new();
+    BLOCK.get_or_init(|| {
+        let home = home();
+        let pieces = pieces(|[x, y, _]| x == 1 && y <= 0);
+        let stickers: Vec<u8> = pieces.iter().map(|&i| home[i]).collect();
+        let size = pattern_size(stickers.len());
+        let table = pattern_table(&stickers, block_turns(), size, pattern_index);
+        (pieces, table)
+    })
+}
+
+/// Last six edges, the U center and one top corner to align the top layer,
+/// and the moves needed to solve them
+fn last_six_edges() -> &'static (Vec<usize>, HashMap<usize, u8>) {
+    static EDGES: OnceLock<(Vec<usize>, HashMap<usize, u8>)> = OnceLock::new();
+    EDGES.get_or_init(|| {
+        let home = home();
+        let mut pieces = pieces(|[x, y, z]| (x == 0 && z != 0) || (y == 1 && z == 0));
+        pieces.push(piece([1, 1, 1]));
+        pieces.push(cube::pieces().len());
+        let solved: Vec<u8> = pieces.iter().map(|&i| home[i]).collect();
+        let mut table = HashMap::from([(pattern_index(&solved), 0)]);
+        let mut frontier = vec![solved];
+        let mut depth = 0;
+        while !frontier.is_empty() {
+            depth += 1;
+            let mut next = vec![];
+            for stickers in &frontier {
+                for (_, moves) in lse_turns() {
+                    let stickers = turn(stickers, moves);
+                    table.entry(pattern_index(&stickers)).or_insert_with(|| {
+                        next.push(stickers);
+                        depth
+                    });
+                }
+            }
+            frontier = next;
+        }
+        (pieces, table)
+    })
+}
+
+fn roux(solve: &mut Solve, cube: &Cube) {
+    match Goal::FirstBlock.solutions(cube, 1).pop() {
+        Some(block) => solve.push(String::from("First Block"), block),
+        None => return,
+    }
+
+    let (block, table) = second_block();
+    let estimate = |stickers: &[u8]| table[pattern_index(stickers)] as usize;
+    match solve.search(block, block_turns(), estimate) {
+        Some(moves) => solve.push(String::from("Second Block"), moves),
+        None => return,
+    }
+
+    // corners oriented then permuted by last layer algorithms, which keep
+    // both blocks whatever they do to the edges
+    let corners = pieces(|[x, y, z]| y == 1 && x != 0 && z != 0);
+    let home = solve.home.clone();
+    let oriented = |stickers: &[u8]| corners.iter().all(|&i| stickers[i] < 9);
+    let permuted = |stickers: &[u8]| corners.iter().all(|&i| stickers[i] == home[i]);
+    let none = (String::new(), Scramble::default());
+    let orient: Vec<(String, Scramble)> = [none.clone()]
+        .into_iter()
+        .chain(
+            AlgSet::Oll.cases()[20..27]
+                .iter()
+                .map(|case| (case.id(), case.setup.inverse())),
+        )
+        .collect();
+    let permute: Vec<(String, Scramble)> = [none]
+        .into_iter()
+        .chain(
+            AlgSet::Pll
+                .cases()
+                .iter()
+                .map(|case| (case.id(), case.setup.inverse())),
+        )
+        .collect();
+    if !permuted(&solve.stickers) {
+        let orientation = solve.find_alg(orient.iter().map(|(n, a)| (n.clone(), a)), oriented);
+        let stickers = solve.stickers.clone();
+        if let Some((_, orientation)) = orientation {
+            solve.stickers = follow(&stickers, orientation.moves());
+            let permutation = solve.find_alg(permute.iter().map(|(n, a)| (n.clone(), a)), permuted);
+            solve.stickers = stickers;
+            if let Some((_, permutation)) = permutation {
+                solve.push(String::from("CMLL"), orientation.then(&permutation));
+            }
+        }
+    }
+
+    let (edges, table) = last_six_edges();
+    let estimate = |stickers: &[u8]| {
+        let moves = table.get(&pattern_index(stickers));
+        moves.map_or(MAX_DEPTH + 1, |&moves| moves as usize)
+    };
+    if let Some(moves) = solve.search(edges, lse_turns(), estimate) {
+        solve.push(String::from("LSE"), moves);
+    }
+}
+
+/// Steps of `method` solving `cube`, as far as they get
+pub fn steps(method: Method, cube: &Cube) -> Vec<Step> {
+    let mut solve = Solve::new(cube);
+    match method {
+        Method::Cfop => cfop(&mut solve, cube),
+        Method::Roux => roux(&mut solve, cube),
+    }
+    solve.steps
+}
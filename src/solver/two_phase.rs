@@ -0,0 +1,371 @@
+//! Kociemba's two-phase algorithm: phase 1 brings the cube into the group
+//! generated by U, D, R2, L2, F2 and B2, phase 2 solves it within that group.
+//! Searching phase 1 solutions of growing length gives shorter and shorter
+//! solutions, the search stops when time is up or once phase 1 alone is as
+//! long as the best solution.
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::scramble::{Direction, Move};
+
+use super::{
+    cubie::{CubieCube, CubieMove},
+    turns,
+};
+
+const MAX_PHASE1: usize = 12;
+const MAX_PHASE2: usize = 18;
+
+const TWISTS: usize = 6561;
+const FLIPS: usize = 4096;
+const SLICES: usize = 495;
+const PERMS: usize = 40320;
+const SLICE_PERMS: usize = 24;
+
+/// Indices in `turns()` of the moves keeping the cube in phase 2's group
+const PHASE2_MOVES: [usize; 10] = [1, 4, 6, 7, 8, 9, 10, 11, 13, 16];
+
+fn cubie_moves() -> &'static Vec<CubieMove> {
+    static MOVES: OnceLock<Vec<CubieMove>> = OnceLock::new();
+    MOVES.get_or_init(|| turns().iter().map(CubieMove::new).collect())
+}
+
+/// Index of `perm` among the permutations of its length
+fn perm_rank(perm: &[u8]) -> usize {
+    (0..perm.len()).fold(0, |rank, i| {
+        let smaller = perm[i + 1..].iter().filter(|&&p| p < perm[i]).count();
+        rank * (perm.len() - i) + smaller
+    })
+}
+
+fn perm_unrank(mut rank: usize, len: usize) -> Vec<u8> {
+    let mut digits = vec![0; len];
+    for i in (0..len).rev() {
+        digits[i] = rank % (len - i);
+        rank /= len - i;
+    }
+    let mut left: Vec<u8> = (0..len as u8).collect();
+    digits.iter().map(|&d| left.remove(d)).collect()
+}
+
+/// Masks of the slots of the 4 middle layer edges in the order of their
+/// index, the first slot being the highest bit so that solved is 0
+fn slice_masks() -> &'static Vec<u16> {
+    static MASKS: OnceLock<Vec<u16>> = OnceLock::new();
+    MASKS.get_or_init(|| (0..1u16 << 12).filter(|m| m.count_ones() == 4).collect())
+}
+
+fn twist(cube: &CubieCube) -> usize {
+    cube.co.iter().rev().fold(0, |t, &o| t * 3 + o as usize)
+}
+
+fn flip(cube: &CubieCube) -> usize {
+    cube.eo.iter().rev().fold(0, |f, &o| f * 2 + o as usize)
+}
+
+fn slice(cube: &CubieCube) -> usize {
+    let mask = (0..12)
+        .filter(|&slot| cube.ep[slot] >= 8)
+        .fold(0, |mask, slot| mask | 1 << (11 - slot));
+    slice_masks().binary_search(&mask).unwrap()
+}
+
+fn corner_perm(cube: &CubieCube) -> usize {
+    perm_rank(&cube.cp)
+}
+
+/// Permutation of the U and D layer edges, once in phase 2's group
+fn edge_perm(cube: &CubieCube) -> usize {
+    perm_rank(&cube.ep[..8])
+}
+
+fn slice_perm(cube: &CubieCube) -> usize {
+    let slice: Vec<u8> = cube.ep[8..].iter().map(|&p| p.saturating_sub(8)).collect();
+    perm_rank(&slice)
+}
+
+/// Coordinate of `cube` after each move of `moves`, for every coordinate
+fn move_table(
+    size: usize,
+    moves: &[usize],
+    cube: impl Fn(usize) -> CubieCube,
+    coordinate: fn(&CubieCube) -> usize,
+) -> Vec<Vec<u16>> {
+    (0..size)
+        .map(|c| {
+            let start = cube(c);
+            moves
+                .iter()
+                .map(|&m| {
+                    let mut cube = start.clone();
+                    cube.apply(&cubie_moves()[m]);
+                    coordinate(&cube) as u16
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Moves needed to solve each pair of coordinates, `a * b_size + b`
+fn prune_table(a: &[Vec<u16>], b: &[Vec<u16>]) -> Vec<u8> {
+    let b_size = b.len();
+    let mut table = vec![u8::MAX; a.len() * b_size];
+    table[0] = 0;
+    let mut frontier = vec![0];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = vec![];
+        for index in frontier {
+            let (ca, cb) = (index / b_size, index % b_size);
+            for m in 0..a[ca].len() {
+                let next_index = a[ca][m] as usize * b_size + b[cb][m] as usize;
+                if table[next_index] == u8::MAX {
+                    table[next_index] = depth;
+                    next.push(next_index);
+                }
+            }
+        }
+        frontier = next;
+    }
+    table
+}
+
+struct Tables {
+    twist: Vec<Vec<u16>>,
+    flip: Vec<Vec<u16>>,
+    slice: Vec<Vec<u16>>,
+    corner_perm: Vec<Vec<u16>>,
+    edge_perm: Vec<Vec<u16>>,
+    slice_perm: Vec<Vec<u16>>,
+    twist_slice: Vec<u8>,
+    flip_slice: Vec<u8>,
+    corner_slice: Vec<u8>,
+    edge_slice: Vec<u8>,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let all: Vec<usize> = (0..turns().len()).collect();
+        let twist = move_table(
+            TWISTS,
+            &all,
+            |t| CubieCube {
+                co: std::array::from_fn(|i| (t / 3usize.pow(i as u32) % 3) as u8),
+                ..CubieCube::default()
+            },
+            twist,
+        );
+        let flip = move_table(
+            FLIPS,
+            &all,
+            |f| CubieCube {
+                eo: std::array::from_fn(|i| (f >> i & 1) as u8),
+                ..CubieCube::default()
+            },
+            flip,
+        );
+        let slice = move_table(
+            SLICES,
+            &all,
+            |s| {
+                let mask = slice_masks()[s];
+                let (mut middle, mut other) = (8.., 0..);
+                CubieCube {
+                    ep: std::array::from_fn(|slot| {
+                        let piece = match mask >> (11 - slot) & 1 {
+                            1 => middle.next(),
+                            _ => other.next(),
+                        };
+                        piece.unwrap() as u8
+                    }),
+                    ..CubieCube::default()
+                }
+            },
+            slice,
+        );
+        let corner_perm = move_table(
+            PERMS,
+            &PHASE2_MOVES,
+            |p| {
+                let perm = perm_unrank(p, 8);
+                CubieCube {
+                    cp: std::array::from_fn(|i| perm[i]),
+                    ..CubieCube::default()
+                }
+            },
+            corner_perm,
+        );
+        let edge_perm = move_table(
+            PERMS,
+            &PHASE2_MOVES,
+            |p| {
+                let perm = perm_unrank(p, 8);
+                CubieCube {
+                    ep: std::array::from_fn(|i| perm.get(i).copied().unwrap_or(i as u8)),
+                    ..CubieCube::default()
+                }
+            },
+            edge_perm,
+        );
+        let slice_perm = move_table(
+            SLICE_PERMS,
+            &PHASE2_MOVES,
+            |p| {
+                let perm = perm_unrank(p, 4);
+                CubieCube {
+                    ep: std::array::from_fn(|i| match i {
+                        0..=7 => i as u8,
+                        _ => perm[i - 8] + 8,
+                    }),
+                    ..CubieCube::default()
+                }
+            },
+            slice_perm,
+        );
+        Tables {
+            twist_slice: prune_table(&twist, &slice),
+            flip_slice: prune_table(&flip, &slice),
+            corner_slice: prune_table(&corner_perm, &slice_perm),
+            edge_slice: prune_table(&edge_perm, &slice_perm),
+            twist,
+            flip,
+            slice,
+            corner_perm,
+            edge_perm,
+            slice_perm,
+        }
+    })
+}
+
+struct Search {
+    start: CubieCube,
+    path: Vec<Move>,
+    /// Move indices of `path`
+    indices: Vec<usize>,
+    best: Option<Vec<Move>>,
+    deadline: Instant,
+}
+
+impl Search {
+    fn done(&self) -> bool {
+        self.best.is_some() && Instant::now() > self.deadline
+    }
+
+    /// Solutions have to be shorter than this to be worth looking for
+    fn bound(&self) -> usize {
+        self.best
+            .as_ref()
+            .map_or(MAX_PHASE1 + MAX_PHASE2 + 1, |best| best.len())
+    }
+
+    fn phase1(&mut self, twist: usize, flip: usize, slice: usize, depth: usize) {
+        let t = tables();
+        if self.path.len() + depth >= self.bound() {
+            return;
+        }
+        if depth == 0 {
+            // a last move of phase 2's group would be found by phase 2 anyway
+            let ends_in_group = self
+                .indices
+                .last()
+                .is_some_and(|m| PHASE2_MOVES.contains(m));
+            if twist == 0 && flip == 0 && slice == 0 && !ends_in_group {
+                self.start_phase2();
+            }
+            return;
+        }
+        let estimate =
+            t.twist_slice[twist * SLICES + slice].max(t.flip_slice[flip * SLICES + slice]);
+        if estimate as usize > depth {
+            return;
+        }
+        for (m, (turn, _)) in turns().iter().enumerate() {
+            if !Direction::can_follow(&self.path, turn.direction) {
+                continue;
+            }
+            self.path.push(*turn);
+            self.indices.push(m);
+            self.phase1(
+                t.twist[twist][m] as usize,
+                t.flip[flip][m] as usize,
+                t.slice[slice][m] as usize,
+                depth - 1,
+            );
+            self.path.pop();
+            self.indices.pop();
+            if self.done() {
+                return;
+            }
+        }
+    }
+
+    fn start_phase2(&mut self) {
+        let mut cube = self.start.clone();
+        for &m in &self.indices {
+            cube.apply(&cubie_moves()[m]);
+        }
+        let (corners, edges, slice) = (corner_perm(&cube), edge_perm(&cube), slice_perm(&cube));
+        let phase1 = self.path.len();
+        let max_depth = MAX_PHASE2.min(self.bound() - phase1 - 1);
+        for depth in 0..=max_depth {
+            if self.phase2(corners, edges, slice, depth) {
+                self.best = Some(self.path.clone());
+                self.path.truncate(phase1);
+                return;
+            }
+        }
+    }
+
+    fn phase2(&mut self, corners: usize, edges: usize, slice: usize, depth: usize) -> bool {
+        let t = tables();
+        let estimate = t.corner_slice[corners * SLICE_PERMS + slice]
+            .max(t.edge_slice[edges * SLICE_PERMS + slice]);
+        if estimate as usize > depth {
+            return false;
+        }
+        if depth == 0 {
+            return true;
+        }
+        for (i, &m) in PHASE2_MOVES.iter().enumerate() {
+            let turn = turns()[m].0;
+            if !Direction::can_follow(&self.path, turn.direction) {
+                continue;
+            }
+            self.path.push(turn);
+            let found = self.phase2(
+                t.corner_perm[corners][i] as usize,
+                t.edge_perm[edges][i] as usize,
+                t.slice_perm[slice][i] as usize,
+                depth - 1,
+            );
+            if found {
+                return true;
+            }
+            self.path.pop();
+        }
+        false
+    }
+}
+
+/// The shortest solution of `cube` found within `time`, or the first one
+/// found when that takes longer
+pub fn solve(cube: &CubieCube, time: Duration) -> Vec<Move> {
+    let mut search = Search {
+        start: cube.clone(),
+        path: vec![],
+        indices: vec![],
+        best: None,
+        deadline: Instant::now() + time,
+    };
+    let (twist, flip, slice) = (twist(cube), flip(cube), slice(cube));
+    for depth in 0..=MAX_PHASE1 {
+        search.phase1(twist, flip, slice, depth);
+        if search.done() || search.bound() <= depth + 1 {
+            break;
+        }
+    }
+    search.best.unwrap_or_default()
+}
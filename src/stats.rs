@@ -1,4 +1,8 @@
-use crate::history::{Entry, SolveTime};
+use crate::{
+    config::SessionConfig,
+    history::{Entry, MultiBld, Penalty, SolveTime},
+    round::RoundConfig,
+};
 use std::ops::{Add, Div};
 use std::time::Duration;
 
@@ -75,3 +79,341 @@ pub fn stats(entries: Vec<&Entry>) -> Vec<Vec<String>> {
     ]);
     result
 }
+
+/// Mean of each phase over the entries split into `session.phases()` phases
+pub fn phase_stats(entries: &[&Entry], session: &SessionConfig) -> Vec<Vec<String>> {
+    let phases = session.phases();
+    if phases < 2 {
+        return vec![];
+    }
+    let split: Vec<&Vec<Duration>> = entries
+        .iter()
+        .map(|entry| &entry.splits)
+        .filter(|splits| splits.len() == phases)
+        .collect();
+    if split.is_empty() {
+        return vec![];
+    }
+
+    let mut result = vec![vec![]];
+    for phase in 0..phases {
+        let total = split
+            .iter()
+            .fold(Duration::from_secs(0), |a, splits| a.add(splits[phase]));
+        result.push(vec![
+            session.phase_name(phase),
+            format!("{:?}", split.last().unwrap()[phase]),
+            format!("{:?}", total.div(split.len() as u32)),
+        ]);
+    }
+    result
+}
+
+fn format_result(d: Option<Duration>) -> String {
+    match d {
+        Some(d) => format!("{:?}", d),
+        None => String::from("DNF"),
+    }
+}
+
+/// Blindfolded stats over all entries including DNFs: mean and best of 3 and
+/// the success rate. A mean of 3 with a DNF is a DNF.
+pub fn bld_stats(entries: &[&Entry]) -> Vec<Vec<String>> {
+    if entries.is_empty() {
+        return vec![];
+    }
+    let results: Vec<Option<Duration>> = entries
+        .iter()
+        .map(|entry| match entry.penalty {
+            Penalty::No | Penalty::Time => Some(entry.time.0),
+            _ => None,
+        })
+        .collect();
+    let best = |a: Option<Duration>, b: Option<Duration>| match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        _ => a.or(b),
+    };
+
+    let single = results.iter().copied().fold(None, best);
+    let mo3: Vec<Option<Duration>> = results
+        .windows(3)
+        .map(|w| {
+            w.iter()
+                .copied()
+                .collect::<Option<Vec<Duration>>>()
+                .map(|w| {
+                    w.iter()
+                        .fold(Duration::from_secs(0), |a, &d| a.add(d))
+                        .div(3)
+                })
+        })
+        .collect();
+    let bo3: Vec<Option<Duration>> = results
+        .windows(3)
+        .map(|w| w.iter().copied().fold(None, best))
+        .collect();
+    let successes = results.iter().filter(|r| r.is_some()).count();
+
+    let mut result = vec![vec![
+        String::from("Time"),
+        format_result(*results.last().unwrap()),
+        format_result(single),
+    ]];
+    for (name, avgs) in [("Mo3", mo3), ("Bo3", bo3)] {
+        result.push(vec![
+            String::from(name),
+            avgs.last()
+                .map(|&d| format_result(d))
+                .unwrap_or_else(|| String::from("-")),
+            avgs.iter()
+                .copied()
+                .fold(None, best)
+                .map(|d| format!("{:?}", d))
+                .unwrap_or_else(|| String::from("-")),
+        ]);
+    }
+    result.push(vec![]);
+    result.push(vec![
+        String::from("Success"),
+        format!("{}/{}", successes, results.len()),
+        format!("{:.1}%", successes as f64 * 100.0 / results.len() as f64),
+    ]);
+    result
+}
+
+/// Multi-blind attempts ranked by points, then by time
+pub fn multi_stats(entries: &[&Entry]) -> Vec<Vec<String>> {
+    let attempts: Vec<(&Entry, MultiBld)> = entries
+        .iter()
+        .filter_map(|&entry| entry.multi.map(|multi| (entry, multi)))
+        .collect();
+    let format = |(entry, multi): &(&Entry, MultiBld)| {
+        if multi.is_dnf() {
+            return format!("DNF ({})", multi);
+        }
+        let secs = entry.time.0.as_secs();
+        format!("{} {}:{:0>2}", multi, secs / 60, secs % 60)
+    };
+    let latest = match attempts.last() {
+        Some(latest) => latest,
+        None => return vec![],
+    };
+    let best = attempts
+        .iter()
+        .filter(|(_, multi)| !multi.is_dnf())
+        .min_by_key(|(entry, multi)| (-multi.points(), entry.time.0));
+    let successes = attempts.iter().filter(|(_, multi)| !multi.is_dnf()).count();
+
+    vec![
+        vec![
+            String::from("Result"),
+            format(latest),
+            best.map(format).unwrap_or_else(|| String::from("-")),
+        ],
+        vec![
+            String::from("Points"),
+            latest.1.points().to_string(),
+            best.map(|(_, multi)| multi.points().to_string())
+                .unwrap_or_else(|| String::from("-")),
+        ],
+        vec![],
+        vec![
+            String::from("Success"),
+            format!("{}/{}", successes, attempts.len()),
+            String::from(""),
+        ],
+    ]
+}
+
+/// Fewest moves results are move counts, a mean of 3 with a DNF is a DNF
+pub fn fmc_stats(entries: &[&Entry]) -> Vec<Vec<String>> {
+    if entries.is_empty() {
+        return vec![];
+    }
+    let results: Vec<Option<usize>> = entries
+        .iter()
+        .map(|entry| match entry.penalty {
+            Penalty::No | Penalty::Time => entry.moves.as_ref().map(|m| m.move_count()),
+            _ => None,
+        })
+        .collect();
+    let format = |moves: Option<f64>| match moves {
+        Some(moves) => format!("{:.2}", moves),
+        None => String::from("DNF"),
+    };
+    let solved: Vec<usize> = results.iter().flatten().copied().collect();
+    let mo3: Vec<Option<f64>> = results
+        .windows(3)
+        .map(|w| {
+            w.iter()
+                .copied()
+                .collect::<Option<Vec<usize>>>()
+                .map(|w| w.iter().sum::<usize>() as f64 / 3.0)
+        })
+        .collect();
+
+    vec![
+        vec![
+            String::from("Moves"),
+            results
+                .last()
+                .unwrap()
+                .map_or_else(|| String::from("DNF"), |moves| moves.to_string()),
+            solved
+                .iter()
+                .min()
+                .map_or_else(|| String::from("-"), |moves| moves.to_string()),
+        ],
+        vec![
+            String::from("Mo3"),
+            mo3.last()
+                .map_or_else(|| String::from("-"), |&moves| format(moves)),
+            mo3.iter()
+                .flatten()
+                .copied()
+                .reduce(f64::min)
+                .map_or_else(|| String::from("-"), |moves| format(Some(moves))),
+        ],
+        vec![],
+        vec![
+            String::from("Mean"),
+            String::from(""),
+            match solved.len() {
+                0 => String::from("-"),
+                n => format(Some(solved.iter().sum::<usize>() as f64 / n as f64)),
+            },
+        ],
+        vec![
+            String::from("Success"),
+            format!("{}/{}", solved.len(), results.len()),
+            format!("{:.1}%", solved.len() as f64 * 100.0 / results.len() as f64),
+        ],
+    ]
+}
+
+/// Planned step move counts and how many moves they are over the optimal
+/// solution
+pub fn plan_stats(entries: &[&Entry]) -> Vec<Vec<String>> {
+    let attempts: Vec<(usize, usize)> = entries
+        .iter()
+        .filter_map(|entry| {
+            let moves = entry.moves.as_ref()?.move_count();
+            Some((moves, moves.saturating_sub(entry.optimal?)))
+        })
+        .collect();
+    let &(moves, extra) = match attempts.last() {
+        Some(latest) => latest,
+        None => return vec![],
+    };
+    let optimal = attempts.iter().filter(|(_, extra)| *extra == 0).count();
+    let total_extra: usize = attempts.iter().map(|(_, extra)| extra).sum();
+
+    vec![
+        vec![
+            String::from("Moves"),
+            moves.to_string(),
+            attempts
+                .iter()
+                .map(|(moves, _)| moves)
+                .min()
+                .unwrap()
+                .to_string(),
+        ],
+        vec![
+            String::from("Extra"),
+            format!("+{}", extra),
+            format!(
+                "+{}",
+                attempts.iter().map(|(_, extra)| extra).min().unwrap()
+            ),
+        ],
+        vec![],
+        vec![
+            String::from("Mean Extra"),
+            String::from(""),
+            format!("+{:.2}", total_extra as f64 / attempts.len() as f64),
+        ],
+        vec![
+            String::from("Optimal"),
+            format!("{}/{}", optimal, attempts.len()),
+            format!("{:.1}%", optimal as f64 * 100.0 / attempts.len() as f64),
+        ],
+    ]
+}
+
+/// Official results of competition simulation rounds, the current round is
+/// the latest one
+pub fn round_stats(round: &RoundConfig, rounds: &[Vec<&Entry>]) -> Vec<Vec<String>> {
+    let results: Vec<(Option<Duration>, Option<Option<Duration>>)> =
+        rounds.iter().map(|solves| round.result(solves)).collect();
+    let (single, average) = match results.last() {
+        Some(&latest) => latest,
+        None => return vec![],
+    };
+    let best_single = results.iter().filter_map(|(single, _)| *single).min();
+    let best_average = results
+        .iter()
+        .filter_map(|(_, average)| average.flatten())
+        .min();
+    let or_none = |d: Option<Duration>| d.map_or_else(|| String::from("-"), |d| format!("{:?}", d));
+
+    vec![
+        vec![
+            String::from("Single"),
+            format_result(single),
+            or_none(best_single),
+        ],
+        vec![
+            round.format.to_string(),
+            average.map_or_else(|| String::from("-"), format_result),
+            or_none(best_average),
+        ],
+        vec![],
+        vec![
+            String::from("Rounds"),
+            String::from(""),
+            rounds.len().to_string(),
+        ],
+    ]
+}
+
+/// Mean time and recognition time of each algorithm trainer case, slowest
+/// case first so that the weakest ones are on top
+pub fn case_stats(entries: &[&Entry]) -> Vec<Vec<String>> {
+    let mut cases: Vec<(&str, Vec<&Entry>)> = vec![];
+    for &entry in entries {
+        let case = match &entry.case {
+            Some(case) => case.as_str(),
+            None => continue,
+        };
+        match cases.iter_mut().find(|(name, _)| *name == case) {
+            Some((_, solves)) => solves.push(entry),
+            None => cases.push((case, vec![entry])),
+        }
+    }
+    let mean = |times: &[Duration]| match times.len() {
+        0 => Duration::from_secs(0),
+        n => times.iter().sum::<Duration>() / n as u32,
+    };
+    let mut rows: Vec<(Duration, Vec<String>)> = cases
+        .iter()
+        .map(|(case, solves)| {
+            let times: Vec<Duration> = solves.iter().map(|entry| entry.time.0).collect();
+            let recognition: Vec<Duration> = solves
+                .iter()
+                .filter_map(|entry| entry.splits.first().copied())
+                .collect();
+            let time = mean(&times);
+            (
+                time,
+                vec![
+                    format!("{} ({})", case, solves.len()),
+                    format!("{:?}", time),
+                    format!("{:?}", mean(&recognition)),
+                ],
+            )
+        })
+        .collect();
+    rows.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+    rows.into_iter().map(|(_, row)| row).collect()
+}
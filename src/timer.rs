@@ -6,11 +6,13 @@ pub enum State {
     Inactive,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct Timer {
     pub state: State,
     time: SystemTime,
     pub result: Duration,
+    /// Time since the start at which each split was recorded
+    pub splits: Vec<Duration>,
 }
 
 impl Timer {
@@ -19,12 +21,28 @@ impl Timer {
             state: State::Inactive,
             time: SystemTime::now(),
             result: Duration::new(0, 0),
+            splits: vec![],
         }
     }
 
     pub fn start(&mut self) {
         self.time = SystemTime::now();
         self.state = State::Active;
+        self.splits.clear();
+    }
+
+    /// Starts a timer that has already been running for `elapsed`, e.g. when
+    /// an external timer tells us it started
+    pub fn start_from(&mut self, elapsed: Duration) {
+        self.time = SystemTime::now() - elapsed;
+        self.state = State::Active;
+        self.splits.clear();
+    }
+
+    /// Stops the timer with a result measured elsewhere
+    pub fn stop_at(&mut self, result: Duration) {
+        self.result = result;
+        self.state = State::Inactive;
     }
 
     pub fn stop(&mut self) {
@@ -32,10 +50,38 @@ impl Timer {
         self.state = State::Inactive;
     }
 
+    /// Ends the current phase of a multi-phase solve
+    pub fn split(&mut self) {
+        self.splits.push(self.elapsed());
+    }
+
+    /// Duration of each phase, empty unless splits were recorded
+    pub fn phases(&self) -> Vec<Duration> {
+        if self.splits.is_empty() {
+            return vec![];
+        }
+        let mut start = Duration::from_secs(0);
+        self.splits
+            .iter()
+            .chain([self.result].iter())
+            .map(|&end| {
+                let phase = end.saturating_sub(start);
+                start = end;
+                phase
+            })
+            .collect()
+    }
+
+    /// Time since the timer was started
+    pub fn elapsed(&self) -> Duration {
+        self.time.elapsed().unwrap_or_default()
+    }
+
     pub fn reset(&mut self) {
         self.state = State::Inactive;
         self.time = SystemTime::now();
         self.result = Duration::from_secs(0);
+        self.splits.clear();
     }
 }
 
@@ -0,0 +1,302 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use rand::seq::SliceRandom;
+use serde_derive::Deserialize;
+
+use crate::{
+    cube::Cube,
+    scramble::{self, Scramble},
+};
+
+/// Last layer algorithm sets the trainer sets up cases of
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AlgSet {
+    /// Orienting the last layer, 57 cases
+    Oll,
+    /// Permuting the last layer, 21 cases
+    Pll,
+    /// Solving the corners of a last layer with oriented edges, 42 cases
+    Coll,
+    /// Solving a last layer with oriented edges in one look, 472 cases
+    Zbll,
+}
+
+impl fmt::Display for AlgSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            AlgSet::Oll => "OLL",
+            AlgSet::Pll => "PLL",
+            AlgSet::Coll => "COLL",
+            AlgSet::Zbll => "ZBLL",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct TrainerConfig {
+    /// Sets the cases are picked from
+    pub sets: Vec<AlgSet>,
+}
+
+impl Default for TrainerConfig {
+    fn default() -> Self {
+        TrainerConfig {
+            sets: vec![AlgSet::Pll],
+        }
+    }
+}
+
+const OLL: [&str; 57] = [
+    "R U2 R2 F R F' U2 R' F R F'",
+    "F R U R' U' F' f R U R' U' f'",
+    "f R U R' U' f' U' F R U R' U' F'",
+    "f R U R' U' f' U F R U R' U' F'",
+    "r' U2 R U R' U r",
+    "r U2 R' U' R U' r'",
+    "r U R' U R U2 r'",
+    "l' U' L U' L' U2 l",
+    "R U R' U' R' F R2 U R' U' F'",
+    "R U R' U R' F R F' R U2 R'",
+    "r U R' U R' F R F' R U2 r'",
+    "M' R' U' R U' R' U2 R U' R r'",
+    "F U R U' R2 F' R U R U' R'",
+    "R' F R U R' F' R F U' F'",
+    "r' U' r R' U' R U r' U r",
+    "r U r' R U R' U' r U' r'",
+    "F R' F' R2 r' U R U' R' U' M'",
+    "r U R' U R U2 r2 U' R U' R' U2 r",
+    "r' R U R U R' U' M' R' F R F'",
+    "r U R' U' M2 U R U' R' U' M'",
+    "R U2 R' U' R U R' U' R U' R'",
+    "R U2 R2 U' R2 U' R2 U2 R",
+    "R2 D' R U2 R' D R U2 R",
+    "r U R' U' r' F R F'",
+    "F' r U R' U' r' F R",
+    "R U2 R' U' R U' R'",
+    "R U R' U R U2 R'",
+    "r U R' U' r' R U R U' R'",
+    "R U R' U' R U' R' F' U' F R U R'",
+    "F R' F R2 U' R' U' R U R' F2",
+    "R' U' F U R U' R' F' R",
+    "L U F' U' L' U L F L'",
+    "R U R' U' R' F R F'",
+    "R U R2 U' R' F R U R U' F'",
+    "R U2 R2 F R F' R U2 R'",
+    "L' U' L U' L' U L U L F' L' F",
+    "F R' F' R U R U' R'",
+    "R U R' U R U' R' U' R' F R F'",
+    "L F' L' U' L U F U' L'",
+    "R' F R U R' U' F' U R",
+    "R U R' U R U2 R' F R U R' U' F'",
+    "R' U' R U' R' U2 R F R U R' U' F'",
+    "F' U' L' U L F",
+    "F U R U' R' F'",
+    "F R U R' U' F'",
+    "R' U' R' F R F' U R",
+    "R' U' R' F R F' R' F R F' U R",
+    "F R U R' U' R U R' U' F'",
+    "r U' r2 U r2 U r2 U' r",
+    "r' U r2 U' r2 U' r2 U r'",
+    "F U R U' R' U R U' R' F'",
+    "R U R' U R U' B U' B' R'",
+    "l' U2 L U L' U' L U L' U l",
+    "r U2 R' U' R U R' U' R U' r'",
+    "R' F R U R U' R2 F' R2 U' R' U R U R'",
+    "r' U' r U' R' U R U' R' U R r' U r",
+    "R U R' U' M' U R U' r'",
+];
+
+const PLL: [(&str, &str); 21] = [
+    ("Aa", "R' F R' B2 R F' R' B2 R2"),
+    ("Ab", "R2 B2 R F R' B2 R F' R"),
+    ("E", "x' R U' R' D R U R' D' R U R' D R U' R' D' x"),
+    ("F", "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R"),
+    ("Ga", "R2 U R' U R' U' R U' R2 U' D R' U R D'"),
+    ("Gb", "R' U' R U D' R2 U R' U R U' R U' R2 D"),
+    ("Gc", "R2 U' R U' R U R' U R2 U D' R U' R' D"),
+    ("Gd", "R U R' U' D R2 U' R U' R' U R' U R2 D'"),
+    ("H", "M2 U M2 U2 M2 U M2"),
+    ("Ja", "R' U L' U2 R U' R' U2 R L"),
+    ("Jb", "R U R' F' R U R' U' R' F R2 U' R'"),
+    (
+        "Na",
+        "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'",
+    ),
+    ("Nb", "R' U R U' R' F' U' F R U R' F R' F' R U' R"),
+    ("Ra", "R U' R' U' R U R D R' U' R D' R' U2 R'"),
+    ("Rb", "R2 F R U R U' R' F' R U2 R' U2 R"),
+    ("T", "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    ("Ua", "M2 U M U2 M' U M2"),
+    ("Ub", "M2 U' M U2 M' U' M2"),
+    ("V", "R' U R' U' B' R' B2 U' B' U B' R B R"),
+    ("Y", "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    ("Z", "M' U M2 U M2 U M' U2 M2"),
+];
+
+/// Corner orientation cases with every edge oriented, named after their
+/// shape, and their number in `OLL`
+const OCLL: [(&str, usize); 7] = [
+    ("H", 21),
+    ("Pi", 22),
+    ("U", 23),
+    ("T", 24),
+    ("L", 25),
+    ("AS", 26),
+    ("S", 27),
+];
+
+/// A last layer case and the moves setting it up on a solved cube
+pub struct Case {
+    pub set: AlgSet,
+    pub name: String,
+    pub setup: Scramble,
+}
+
+fn alg(moves: &str) -> Scramble {
+    moves.parse().unwrap()
+}
+
+/// Turns of the top layer adjusting it before or after an algorithm
+pub fn auf(quarters: usize) -> Scramble {
+    alg(["", "U", "U2", "U'"][quarters % 4])
+}
+
+/// Cases are the same if they only differ by a turn of the top layer before
+/// or after the algorithm, so they are compared by the least of their states
+fn case_key(setup: &Scramble, mask: &impl Fn(usize, u8) -> u8) -> Cube {
+    (0..4)
+        .flat_map(|pre| {
+            let mut cube = Cube::scrambled(&auf(pre).then(setup));
+            (0..4).map(move |_| {
+                cube.apply_all(auf(1).moves());
+                cube.map_stickers(mask)
+            })
+        })
+        .min()
+        .unwrap()
+}
+
+/// Cases of a set whose algorithms have a name
+fn named_cases(set: AlgSet, algs: &[(String, &str)]) -> Vec<Case> {
+    algs.iter()
+        .map(|(name, moves)| Case {
+            set,
+            name: name.clone(),
+            setup: alg(moves).inverse(),
+        })
+        .collect()
+}
+
+/// Every case with oriented edges, set up by corner orientation then
+/// permutation algorithms. Cases are named after their corner orientation
+/// shape and numbered within it. `corners_only` leaves out the edges when
+/// telling cases apart, `oriented` keeps cases whose corners are oriented.
+fn generated_cases(set: AlgSet, corners_only: bool, oriented: bool) -> Vec<Case> {
+    let mask = |i: usize, face: u8| match i % 9 {
+        1 | 3 | 5 | 7 if corners_only => 6,
+        _ => face,
+    };
+    let solved = case_key(&Scramble::default(), &mask);
+    let shapes = oriented
+        .then_some(("O", None))
+        .into_iter()
+        .chain(OCLL.iter().map(|&(shape, oll)| (shape, Some(oll))));
+
+    let mut cases = vec![];
+    for (shape, oll) in shapes {
+        let orient = oll.map_or_else(Scramble::default, |oll| alg(OLL[oll - 1]));
+        let mut keys: Vec<Cube> = vec![];
+        let mut shape_cases: Vec<Case> = vec![];
+        for permute in PLL
+            .iter()
+            .map(|&(_, moves)| alg(moves))
+            .chain([Scramble::default()])
+        {
+            for quarters in 0..4 {
+                let setup = orient.then(&auf(quarters)).then(&permute).inverse();
+                let key = case_key(&setup, &mask);
+                if key == solved {
+                    continue;
+                }
+                match keys.iter().position(|k| *k == key) {
+                    Some(i) if shape_cases[i].setup.moves().len() > setup.moves().len() => {
+                        shape_cases[i].setup = setup
+                    }
+                    Some(_) => {}
+                    None => {
+                        keys.push(key);
+                        shape_cases.push(Case {
+                            set,
+                            name: format!("{} {}", shape, shape_cases.len() + 1),
+                            setup,
+                        });
+                    }
+                }
+            }
+        }
+        cases.extend(shape_cases);
+    }
+    cases
+}
+
+impl AlgSet {
+    pub fn cases(&self) -> &'static [Case] {
+        static CASES: [OnceLock<Vec<Case>>; 4] = [
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+            OnceLock::new(),
+        ];
+        CASES[*self as usize].get_or_init(|| match self {
+            AlgSet::Oll => named_cases(
+                *self,
+                &OLL.iter()
+                    .enumerate()
+                    .map(|(i, &moves)| ((i + 1).to_string(), moves))
+                    .collect::<Vec<(String, &str)>>(),
+            ),
+            AlgSet::Pll => named_cases(
+                *self,
+                &PLL.iter()
+                    .map(|&(name, moves)| (String::from(name), moves))
+                    .collect::<Vec<(String, &str)>>(),
+            ),
+            AlgSet::Coll => generated_cases(*self, true, true),
+            AlgSet::Zbll => generated_cases(*self, false, false),
+        })
+    }
+}
+
+impl Case {
+    /// Name including the set, e.g. PLL Ua
+    pub fn id(&self) -> String {
+        format!("{} {}", self.set, self.name)
+    }
+
+    pub fn scramble(&self) -> Scramble {
+        random_aufs(&self.setup)
+    }
+}
+
+/// `setup` seen from a random side, finishing with a random turn of the top
+/// layer
+pub fn random_aufs(setup: &Scramble) -> Scramble {
+    let (pre, post) = scramble::with_rng(|rng| {
+        let quarters = [0, 1, 2, 3];
+        (
+            *quarters.choose(rng).unwrap(),
+            *quarters.choose(rng).unwrap(),
+        )
+    });
+    auf(pre).then(setup).then(&auf(post))
+}
+
+/// Random case of the given sets, None without any set
+pub fn random_case(sets: &[AlgSet]) -> Option<&'static Case> {
+    let cases: Vec<&'static Case> = sets.iter().flat_map(|set| set.cases()).collect();
+    scramble::with_rng(|rng| cases.choose(rng).copied())
+}
@@ -1,7 +1,14 @@
-use crate::app::{App, AppState};
+use crate::{
+    app::{App, AppState, Prompt},
+    settings::SETTINGS,
+};
 use tui::{backend::Backend, Frame};
 
-use super::{draw_confirm_dialog, draw_help, draw_idle, draw_inspecting, draw_timer};
+use super::{
+    draw_confirm_dialog, draw_detail, draw_drill, draw_fmc, draw_help, draw_history_report,
+    draw_idle, draw_inspecting, draw_library, draw_plan_result, draw_profiles, draw_round_summary,
+    draw_settings, draw_solution, draw_timer, draw_typing,
+};
 
 pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) {
     match app.state {
@@ -9,7 +16,47 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) {
         AppState::Idle => draw_idle(f, app),
         AppState::Inspecting | AppState::KeyHold => draw_inspecting(f, app),
         AppState::Timer => draw_timer(f, app),
+        AppState::Typing(prompt) => {
+            let setting = &SETTINGS[app.setting_selected];
+            let (setting, hint) = (setting.name(), setting.kind.hint());
+            let (title, example) = match prompt {
+                Prompt::Time => ("Enter Time", "e.g. 12.34, 1:02.55, DNF(15.20) or +2 13.01"),
+                Prompt::Moves => ("Enter Solution", "e.g. R U R' U' or R@120 U@250"),
+                Prompt::Cubes => ("Multi-Blind", "How many cubes will you attempt?"),
+                Prompt::SolvedCubes => ("Multi-Blind", "How many cubes did you solve?"),
+                Prompt::Alg => (
+                    "Algorithm Library",
+                    "e.g. PLL Ua: M2 U M U2 M' U M2; 2x2 CLL H 1: R2 U2 R U2 R2",
+                ),
+                Prompt::Plan => (
+                    "Enter Your Plan",
+                    "e.g. F' R D2 L' for the step set in [plan]",
+                ),
+                Prompt::Profile => ("New Profile", "e.g. alex, starting with the default config"),
+                Prompt::Setting => (setting.as_str(), hint.as_str()),
+            };
+            match prompt {
+                Prompt::Moves => draw_detail(f, app),
+                Prompt::Alg => draw_library(f, app),
+                Prompt::Profile => draw_profiles(f, app),
+                Prompt::Setting => draw_settings(f, app),
+                _ => draw_idle(f, app),
+            }
+            draw_typing(f, app, title, example);
+        }
+        AppState::Detail => draw_detail(f, app),
+        AppState::Fmc => draw_fmc(f, app),
+        AppState::RoundSummary => draw_round_summary(f, app),
+        AppState::PlanResult => draw_plan_result(f, app),
+        AppState::Solution => draw_solution(f, app),
+        AppState::Profiles => draw_profiles(f, app),
+        AppState::Settings => draw_settings(f, app),
+        AppState::Library => draw_library(f, app),
+        AppState::Drill | AppState::Recall => draw_drill(f, app),
         AppState::Confirm(s) => draw_confirm_dialog(f, s, app),
         _ => {}
     }
+    if let Some(report) = &app.history_report {
+        draw_history_report(f, report);
+    }
 }
@@ -8,10 +8,13 @@ use tui::{
     Frame,
 };
 
-use super::draw_idle;
+use super::{draw_idle, draw_library};
 
 pub fn draw_confirm_dialog<B: Backend>(f: &mut Frame<B>, s: &str, app: &App) {
-    draw_idle(f, app);
+    match s {
+        "delete case" => draw_library(f, app),
+        _ => draw_idle(f, app),
+    }
 
     let area = centered_rect(50, 20, f.size());
 
@@ -21,6 +24,7 @@ pub fn draw_confirm_dialog<B: Backend>(f: &mut Frame<B>, s: &str, app: &App) {
         "dnf" => "Are you sure you wish to mark last entry as DNF (y/n) ?",
         "time" => "Are you sure you wish to makr last entry as Time penalty, i.e. +2s (y/n) ?",
         "clear" => "Are you sure you wish to clear all data (y/n) ?",
+        "delete case" => "Are you sure you wish to delete this case and its algorithms (y/n) ?",
         _ => "Are you sure (y/n) ?",
     };
     let text = Spans::from(vec![Span::styled(
@@ -37,7 +41,7 @@ pub fn draw_confirm_dialog<B: Backend>(f: &mut Frame<B>, s: &str, app: &App) {
     f.render_widget(paragraph, area);
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
@@ -0,0 +1,177 @@
+use crate::app::App;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+
+pub fn draw_detail<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let entries = app.history.entries();
+    let entry = match entries.get(app.selected) {
+        Some(entry) => entry,
+        None => return,
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(50),
+                Constraint::Percentage(40),
+                Constraint::Percentage(10),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    let label = Style::default().add_modifier(Modifier::BOLD);
+    let mut text = vec![
+        Spans::from(vec![
+            Span::styled("Time: ", label),
+            Span::styled(
+                entry.time.to_string(),
+                Style::default().fg(Color::LightGreen),
+            ),
+            Span::raw(format!("  {}", entry.penalty)),
+        ]),
+        Spans::from(vec![
+            Span::styled("Date: ", label),
+            Span::raw(entry.date.format("%Y-%m-%d %H:%M:%S").to_string()),
+        ]),
+        Spans::from(vec![
+            Span::styled("Scramble: ", label),
+            Span::styled(
+                entry.scramble.to_string(),
+                Style::default().fg(Color::LightRed),
+            ),
+        ]),
+    ];
+    if let Some(case) = &entry.case {
+        text.push(Spans::from(vec![
+            Span::styled("Case: ", label),
+            Span::raw(case.as_str()),
+        ]));
+    }
+    // the rest of a multi puzzle attempt's scrambles
+    for (i, scramble) in entry.scrambles.iter().enumerate().skip(1) {
+        text.push(Spans::from(vec![
+            Span::styled(format!("Scramble {}: ", i + 1), label),
+            Span::styled(scramble.to_string(), Style::default().fg(Color::LightRed)),
+        ]));
+    }
+    if !entry.splits.is_empty() {
+        let splits = entry
+            .splits
+            .iter()
+            .enumerate()
+            .map(|(i, split)| {
+                format!(
+                    "{} {:.2}s",
+                    app.config.session.phase_name(i),
+                    split.as_secs_f64()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("  ");
+        text.push(Spans::from(vec![
+            Span::styled("Splits: ", label),
+            Span::raw(splits),
+        ]));
+    }
+    text.push(Spans::from(""));
+    match &entry.moves {
+        Some(moves) => {
+            let solution = moves
+                .0
+                .iter()
+                .map(|m| m.m.to_string())
+                .collect::<Vec<String>>()
+                .join(" ");
+            text.push(Spans::from(vec![
+                Span::styled("Solution: ", label),
+                Span::raw(solution),
+            ]));
+            if let Some(optimal) = entry.optimal {
+                text.push(Spans::from(vec![
+                    Span::styled("Optimal: ", label),
+                    Span::raw(format!(
+                        "{} moves (+{})",
+                        optimal,
+                        moves.move_count().saturating_sub(optimal)
+                    )),
+                ]));
+            }
+            text.push(Spans::from(vec![
+                Span::styled("Moves: ", label),
+                Span::raw(format!(
+                    "{}  TPS: {:.2}",
+                    moves.move_count(),
+                    moves.tps(entry.time.0)
+                )),
+            ]));
+        }
+        None => text.push(Spans::from("No reconstruction, press m to type one in")),
+    }
+    let block = Block::default()
+        .title(format!("Solve {} of {}", app.selected + 1, entries.len()))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: true });
+    f.render_widget(paragraph, chunks[0]);
+
+    let steps = entry
+        .moves
+        .as_ref()
+        .map(|moves| moves.cfop_steps(&entry.scramble))
+        .unwrap_or_default();
+    let rows: Vec<Row> = steps
+        .iter()
+        .map(|step| {
+            Row::new(vec![
+                Cell::from(step.name.as_str()),
+                Cell::from(step.moves.to_string()),
+                Cell::from(
+                    step.duration
+                        .map(|d| format!("{:.2}s", d.as_secs_f64()))
+                        .unwrap_or_else(|| String::from("-")),
+                ),
+            ])
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Step", "Moves", "Time"]))
+        .block(
+            Block::default()
+                .title("CFOP Breakdown")
+                .title_alignment(Alignment::Center)
+                .borders(Borders::ALL),
+        )
+        .widths(
+            [
+                Constraint::Percentage(33),
+                Constraint::Percentage(33),
+                Constraint::Percentage(33),
+            ]
+            .as_ref(),
+        )
+        .column_spacing(1);
+    f.render_widget(table, chunks[1]);
+
+    let help_block = Block::default().borders(Borders::ALL);
+    let mut text = vec![Spans::from(
+        "j/k: Next / Previous Solve    m: Type in Solution    s: Solve Scramble    q: Back",
+    )];
+    if let Some(message) = &app.message {
+        text.push(Spans::from(vec![Span::styled(
+            message.as_str(),
+            Style::default().fg(Color::LightRed),
+        )]));
+    }
+    let paragraph = Paragraph::new(text)
+        .block(help_block)
+        .alignment(Alignment::Center);
+    f.render_widget(paragraph, chunks[2]);
+}
@@ -0,0 +1,87 @@
+use crate::{
+    app::{App, AppState},
+    timer::State,
+};
+use cfonts::{render, Fonts, Options};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+pub fn draw_drill<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let (drill, case) = match &app.drill {
+        Some(drill) => match app.library.cases().get(drill.case) {
+            Some(case) => (drill, case),
+            None => return,
+        },
+        None => return,
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(20),
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    let setup_block = Block::default()
+        .title(format!("Setup - {}", case.id()))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(Spans::from(vec![Span::styled(
+        drill.setup.to_string(),
+        Style::default()
+            .fg(Color::LightRed)
+            .add_modifier(Modifier::BOLD),
+    )]))
+    .block(setup_block)
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, chunks[0]);
+
+    let timer_block = Block::default()
+        .title("Timer")
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    let timer_font = render(Options {
+        text: app.timer.to_string(),
+        font: Fonts::FontHuge,
+        ..Options::default()
+    });
+    let mut text = Text::styled(timer_font.text, Style::default().fg(Color::LightGreen));
+    // the algorithms are only shown once the drill is over
+    if app.state == AppState::Recall {
+        for alg in &case.algs {
+            text.extend(Text::styled(
+                alg.to_string(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+        }
+    }
+    let paragraph = Paragraph::new(text)
+        .block(timer_block)
+        .alignment(Alignment::Center);
+    f.render_widget(paragraph, chunks[1]);
+
+    let help_block = Block::default().borders(Borders::ALL);
+    let text = match (&app.state, app.timer.state) {
+        (AppState::Recall, _) => {
+            "How well did you recall it? 1: Forgot    2: Hard    3: Good    4: Easy"
+        }
+        (_, State::Active) => "Press Any Key to Stop Timer",
+        _ => "Press <Space> to Start Timer, q to Stop Drilling",
+    };
+    let paragraph = Paragraph::new(Spans::from(text))
+        .block(help_block)
+        .alignment(Alignment::Center);
+    f.render_widget(paragraph, chunks[2]);
+}
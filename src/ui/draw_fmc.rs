@@ -0,0 +1,77 @@
+use crate::{app::App, reconstruction::Reconstruction};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+pub fn draw_fmc<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(20),
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    let scramble_block = Block::default()
+        .title("Scramble")
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(Spans::from(vec![Span::styled(
+        app.scramble.to_string(),
+        Style::default()
+            .fg(Color::LightRed)
+            .add_modifier(Modifier::BOLD),
+    )]))
+    .block(scramble_block)
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true });
+    f.render_widget(paragraph, chunks[0]);
+
+    let remaining = app.fmc_remaining().as_secs();
+    let moves = match app.input.parse::<Reconstruction>() {
+        Ok(solution) => solution.move_count().to_string(),
+        Err(_) => String::from("?"),
+    };
+    let solution_block = Block::default()
+        .title(format!(
+            "Solution - {} moves - {}:{:0>2} left",
+            moves,
+            remaining / 60,
+            remaining % 60
+        ))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(Spans::from(vec![Span::styled(
+        format!("{}_", app.input),
+        Style::default()
+            .fg(Color::LightGreen)
+            .add_modifier(Modifier::BOLD),
+    )]))
+    .block(solution_block)
+    .wrap(Wrap { trim: false });
+    f.render_widget(paragraph, chunks[1]);
+
+    let help_block = Block::default().borders(Borders::ALL);
+    let mut text = vec![Spans::from(
+        "<Enter> to Submit the Solution, Ctrl <d> to Give Up (DNF)",
+    )];
+    if let Some(err) = &app.input_error {
+        text.push(Spans::from(vec![Span::styled(
+            err.as_str(),
+            Style::default().fg(Color::LightRed),
+        )]));
+    }
+    let paragraph = Paragraph::new(text)
+        .block(help_block)
+        .alignment(Alignment::Center);
+    f.render_widget(paragraph, chunks[2]);
+}
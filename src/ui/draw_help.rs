@@ -25,6 +25,24 @@ pub fn draw_help<B: Backend>(f: &mut Frame<B>) {
         Spans::from("    u: Undo delete from History"),
         Spans::from("    d: Mark last entry as DNF penalty"),
         Spans::from("    t: Mark last entry as Time penalty"),
+        Spans::from("    i: Type in times manually, e.g. 12.34, DNF(15.20) or +2 13.01"),
+        Spans::from("    z: Mark the connected smart cube as solved"),
+        Spans::from("    v: View solve details and reconstruction"),
+        Spans::from("    p: Change the number of phases per solve, any key records a split"),
+        Spans::from("    b: Toggle BLD mode, no inspection with a memo / execution split"),
+        Spans::from("    m: Start a Multi-Blind attempt, e: Export its scrambles"),
+        Spans::from("    f: Toggle Fewest Moves mode, an hour to type in a verified solution"),
+        Spans::from("    l: Toggle Relay mode, any key splits between puzzles, <Space> stops"),
+        Spans::from("    w: Toggle Competition Simulation mode, solving rounds set up in [round]"),
+        Spans::from(
+            "    a: Toggle Algorithm Trainer mode, setting up cases of the sets in [trainer]",
+        ),
+        Spans::from(
+            "    o: Toggle Planning mode, type in the step set in [plan] and compare it with optimal",
+        ),
+        Spans::from("    g: Open the Algorithm Library to add your algorithms and drill due cases"),
+        Spans::from("    P: Switch to another profile with its own config and history"),
+        Spans::from("    S: Open the Settings to edit the config file"),
         Spans::from("    <Space>: Start Inspection -> Start Timer -> Stop Timer"),
         Spans::from("    Ctrl <Space>: Start Timer Without Starting Inspection"),
     ];
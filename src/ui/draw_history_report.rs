@@ -0,0 +1,78 @@
+use crate::history::LoadReport;
+use tui::{
+    backend::Backend,
+    layout::Alignment,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use super::centered_rect;
+
+/// Skipped lines listed before the rest are summed up
+const MAX_LINES: usize = 10;
+
+pub fn draw_history_report<B: Backend>(f: &mut Frame<B>, report: &LoadReport) {
+    let area = centered_rect(70, 60, f.size());
+    let label = Style::default().add_modifier(Modifier::BOLD);
+
+    let mut text = vec![];
+    if let Some(version) = report.newer {
+        text.push(Spans::from(Span::styled(
+            format!(
+                "The history was saved by a newer version (format v{}), it is read-only and new solves won't be saved",
+                version
+            ),
+            Style::default().fg(Color::LightRed),
+        )));
+        text.push(Spans::from(""));
+    }
+    if !report.skipped.is_empty() {
+        text.push(Spans::from(format!(
+            "{} lines of the history couldn't be loaded:",
+            report.skipped.len()
+        )));
+        text.push(Spans::from(""));
+    }
+    text.extend(report.skipped.iter().take(MAX_LINES).map(|(line, reason)| {
+        Spans::from(vec![
+            Span::styled(format!("Line {}: ", line), label),
+            Span::raw(reason.as_str()),
+        ])
+    }));
+    if report.skipped.len() > MAX_LINES {
+        text.push(Spans::from(format!(
+            "and {} more",
+            report.skipped.len() - MAX_LINES
+        )));
+    }
+    if !report.skipped.is_empty() {
+        text.push(Spans::from(""));
+        text.push(match (&report.error, report.newer) {
+            (_, Some(_)) => Spans::from("They were left in the file"),
+            (Some(err), None) => Spans::from(Span::styled(
+                format!(
+                    "They couldn't be kept in {} and will be lost on save: {}",
+                    report.quarantine, err
+                ),
+                Style::default().fg(Color::LightRed),
+            )),
+            (None, None) => Spans::from(format!("They were moved to {}", report.quarantine)),
+        });
+        text.push(Spans::from(""));
+    }
+    text.push(Spans::from("Press Any Key to Continue"));
+
+    let block = Block::default()
+        .title("History")
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .style(Style::default().bg(Color::DarkGray))
+        .block(block)
+        .wrap(Wrap { trim: true });
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
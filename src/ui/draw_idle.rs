@@ -1,4 +1,11 @@
-use crate::{app::App, stats::stats};
+use crate::{
+    app::App,
+    config::Mode,
+    history::Entry,
+    stats::{
+        bld_stats, case_stats, fmc_stats, multi_stats, phase_stats, plan_stats, round_stats, stats,
+    },
+};
 use cfonts::{render, Fonts, Options};
 use chrono::NaiveDateTime;
 use tui::{
@@ -9,7 +16,7 @@ use tui::{
     text::{Span, Spans, Text},
     widgets::{
         Axis, Block, Borders, Cell, Chart, Dataset, List, ListItem, ListState, Paragraph, Row,
-        Table,
+        Table, Wrap,
     },
     Frame,
 };
@@ -32,7 +39,10 @@ pub fn draw_idle<B: Backend>(f: &mut Frame<B>, app: &App) {
         .constraints([Constraint::Percentage(100)].as_ref())
         .split(chunks[0]);
     let left_pane = Block::default()
-        .title("History")
+        .title(match app.history.read_only() {
+            Some(_) => Span::styled("History (Read-Only)", Style::default().fg(Color::LightRed)),
+            None => Span::raw("History"),
+        })
         .title_alignment(Alignment::Center)
         .borders(Borders::ALL);
     let summary = app.history.summarize();
@@ -43,35 +53,88 @@ pub fn draw_idle<B: Backend>(f: &mut Frame<B>, app: &App) {
         .collect();
     let list = List::new(items).block(left_pane);
     let mut state = ListState::default();
-    if summary.len() > 0 {
-        state.select(Some(summary.len() as usize - 1));
+    if !summary.is_empty() {
+        state.select(Some(summary.len() - 1));
     }
     f.render_stateful_widget(list, left_chunks[0], &mut state);
 
+    // make room for every scramble of a multi puzzle attempt, a round's
+    // scrambles are only shown one solve at a time
+    let mode = app.config.session.mode;
+    let show_all = !app.scrambles.is_empty() && mode != Mode::CompSim;
+    let scramble_height = if show_all { 50 } else { 20 };
     let middle_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Percentage(20),
-                Constraint::Percentage(60),
+                Constraint::Percentage(scramble_height),
+                Constraint::Percentage(80 - scramble_height),
                 Constraint::Percentage(20),
             ]
             .as_ref(),
         )
         .split(chunks[1]);
+    let scramble_title = if app.config.smartcube.is_some() && app.cube_ready() {
+        String::from("Scramble (Cube Ready)")
+    } else if mode == Mode::CompSim {
+        format!(
+            "Scramble {} of {}",
+            app.round_solves().len() + 1,
+            app.scrambles.len()
+        )
+    } else if mode == Mode::Trainer {
+        let sets: Vec<String> = app
+            .config
+            .trainer
+            .sets
+            .iter()
+            .map(|set| set.to_string())
+            .collect();
+        format!("Setup ({})", sets.join(", "))
+    } else if mode == Mode::Plan {
+        format!("Scramble (Plan the {})", app.config.plan.goal)
+    } else if let (Some(set), false) = (&app.scramble_set, show_all) {
+        format!(
+            "Scramble {} of {} ({})",
+            app.set_index,
+            set.scrambles.len(),
+            set.name
+        )
+    } else {
+        String::from("Scramble")
+    };
     let middle_top_pane = Block::default()
-        .title("Scramble")
+        .title(scramble_title)
         .title_alignment(Alignment::Center)
         .borders(Borders::ALL);
-    let scramble_text = Spans::from(vec![Span::styled(
-        format!("{}", app.scramble.to_string().as_str()),
-        Style::default()
-            .fg(Color::LightRed)
-            .add_modifier(Modifier::BOLD),
-    )]);
+    let scramble_style = Style::default()
+        .fg(Color::LightRed)
+        .add_modifier(Modifier::BOLD);
+    let scramble_text = if !show_all {
+        vec![Spans::from(vec![Span::styled(
+            app.scramble.to_string(),
+            scramble_style,
+        )])]
+    } else {
+        app.scrambles
+            .iter()
+            .enumerate()
+            .map(|(i, scramble)| {
+                let label = match mode {
+                    Mode::Relay => app.config.session.phase_name(i),
+                    _ => format!("{}.", i + 1),
+                };
+                Spans::from(vec![Span::styled(
+                    format!("{} {}", label, scramble),
+                    scramble_style,
+                )])
+            })
+            .collect()
+    };
     let paragraph = Paragraph::new(scramble_text)
         .block(middle_top_pane)
-        .alignment(Alignment::Center);
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
     f.render_widget(paragraph, middle_chunks[0]);
 
     let middle_middle_pane = Block::default()
@@ -83,18 +146,30 @@ pub fn draw_idle<B: Backend>(f: &mut Frame<B>, app: &App) {
         font: Fonts::FontHuge,
         ..Options::default()
     });
-    let timer_text = Text::styled(
-        format!("{}", timer_font.text),
-        Style::default().fg(Color::LightGreen),
-    );
+    let timer_text = Text::styled(timer_font.text, Style::default().fg(Color::LightGreen));
     let paragraph = Paragraph::new(timer_text)
         .block(middle_middle_pane)
         .alignment(Alignment::Center);
     f.render_widget(paragraph, middle_chunks[1]);
 
-    let middle_bottom_pane = Block::default().borders(Borders::ALL);
+    let read_only = app.read_only_notice();
+    let middle_bottom_pane = Block::default()
+        .title(match (&app.message, &read_only) {
+            (Some(message), _) => Span::raw(message.as_str()),
+            (None, Some(notice)) => {
+                Span::styled(notice.as_str(), Style::default().fg(Color::LightRed))
+            }
+            (None, None) => Span::raw(app.title),
+        })
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
     let table = Table::new(vec![Row::new(vec![
-        "Press <Space> to Start Inspection",
+        match app.config.session.mode {
+            Mode::Bld | Mode::Trainer => "Press <Space> to Start Timer",
+            Mode::Fmc => "Press <Space> to Start Attempt",
+            Mode::Plan => "Press <Space> to Type Your Plan",
+            _ => "Press <Space> to Start Inspection",
+        },
         "Press ? to Show Help",
         "Press q to Quit Application",
     ])])
@@ -116,10 +191,44 @@ pub fn draw_idle<B: Backend>(f: &mut Frame<B>, app: &App) {
         .split(chunks[2]);
 
     let right_top_pane = Block::default()
-        .title("Stats")
+        .title(match app.config.session.mode {
+            Mode::Bld => "BLD Stats",
+            Mode::MultiBld => "Multi-Blind Stats",
+            Mode::Fmc => "FMC Stats",
+            Mode::Relay => "Relay Stats",
+            Mode::CompSim => "Round Stats",
+            Mode::Trainer => "Case Stats",
+            Mode::Plan => "Plan Stats",
+            _ => "Stats",
+        })
         .title_alignment(Alignment::Center)
         .borders(Borders::ALL);
-    let stats = stats(app.history.valid_entries());
+    // each mode only counts its own solves
+    let entries: Vec<&Entry> = app
+        .history
+        .entries()
+        .iter()
+        .filter(|entry| entry.mode == mode)
+        .collect();
+    let valid_entries: Vec<&Entry> = app
+        .history
+        .valid_entries()
+        .into_iter()
+        .filter(|entry| entry.mode == mode)
+        .collect();
+    let mut stats = match mode {
+        Mode::Bld => bld_stats(&entries),
+        Mode::MultiBld => multi_stats(&entries),
+        Mode::Fmc => fmc_stats(&entries),
+        Mode::CompSim => round_stats(&app.config.round, &app.history.rounds()),
+        Mode::Trainer => case_stats(&valid_entries),
+        Mode::Plan => plan_stats(&valid_entries),
+        Mode::Normal | Mode::Relay => stats(valid_entries.clone()),
+    };
+    // the trainer's case stats already have the recognition time
+    if mode != Mode::Trainer {
+        stats.extend(phase_stats(&valid_entries, &app.config.session));
+    }
     let mut rows: Vec<Row> = vec![];
     for stat in stats.iter() {
         let mut row: Vec<Cell> = vec![];
@@ -134,7 +243,10 @@ pub fn draw_idle<B: Backend>(f: &mut Frame<B>, app: &App) {
         rows.push(Row::new(row));
     }
     let table = Table::new(rows)
-        .header(Row::new(vec!["", "Current", "Best"]))
+        .header(Row::new(match mode {
+            Mode::Trainer => vec!["Case", "Mean", "Recognition"],
+            _ => vec!["", "Current", "Best"],
+        }))
         .block(right_top_pane)
         .widths(
             [
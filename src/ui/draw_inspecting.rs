@@ -23,10 +23,7 @@ pub fn draw_inspecting<B: Backend>(f: &mut Frame<B>, app: &App) {
         font: Fonts::FontHuge,
         ..Options::default()
     });
-    let mut timer_text = Text::styled(
-        format!("{}", timer_font.text),
-        Style::default().fg(Color::White),
-    );
+    let mut timer_text = Text::styled(timer_font.text, Style::default().fg(Color::White));
     if app.key_hold.state == CountdownState::Start {
         timer_text.patch_style(Style::default().fg(Color::Green));
     }
@@ -0,0 +1,95 @@
+use crate::app::App;
+use chrono::Utc;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+pub fn draw_library<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(85), Constraint::Percentage(15)].as_ref())
+        .split(f.size());
+
+    let now = Utc::now();
+    let rows: Vec<Row> = app
+        .library
+        .cases()
+        .iter()
+        .map(|case| {
+            let alg = match case.algs.len() {
+                0 | 1 => case
+                    .algs
+                    .first()
+                    .map(|alg| alg.to_string())
+                    .unwrap_or_default(),
+                n => format!("{} (+{})", case.algs[0], n - 1),
+            };
+            let until = case.review.due - now;
+            let due = if until <= chrono::Duration::zero() {
+                String::from("now")
+            } else if until.num_days() > 0 {
+                format!("in {}d", until.num_days())
+            } else {
+                format!("in {}h", until.num_hours() + 1)
+            };
+            Row::new(vec![
+                Cell::from(case.id()),
+                Cell::from(alg),
+                Cell::from(due),
+                Cell::from(
+                    case.review
+                        .last_time
+                        .map(|time| format!("{:.2}s", time.as_secs_f64()))
+                        .unwrap_or_else(|| String::from("-")),
+                ),
+            ])
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Case", "Algorithm", "Due", "Last Drill"]))
+        .block(
+            Block::default()
+                .title(format!(
+                    "Algorithm Library - {} due",
+                    app.library.due().len()
+                ))
+                .title_alignment(Alignment::Center)
+                .borders(Borders::ALL),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(
+            [
+                Constraint::Percentage(20),
+                Constraint::Percentage(55),
+                Constraint::Percentage(10),
+                Constraint::Percentage(15),
+            ]
+            .as_ref(),
+        )
+        .column_spacing(1);
+    let mut state = TableState::default();
+    if !app.library.cases().is_empty() {
+        state.select(Some(app.library_selected));
+    }
+    f.render_stateful_widget(table, chunks[0], &mut state);
+
+    let help_block = Block::default().borders(Borders::ALL);
+    let mut text = vec![Spans::from(
+        "j/k: Select    n: New Case    e: Edit    x: Delete    i: Import [trainer] Sets    d: Drill Due Cases    q: Back",
+    )];
+    if let Some(message) = &app.message {
+        text.push(Spans::from(vec![Span::styled(
+            message.as_str(),
+            Style::default().fg(Color::LightRed),
+        )]));
+    }
+    let paragraph = Paragraph::new(text)
+        .block(help_block)
+        .alignment(Alignment::Center);
+    f.render_widget(paragraph, chunks[1]);
+}
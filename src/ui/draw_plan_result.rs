@@ -0,0 +1,84 @@
+use crate::app::App;
+use tui::{
+    backend::Backend,
+    layout::Alignment,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use super::{centered_rect, draw_idle};
+
+pub fn draw_plan_result<B: Backend>(f: &mut Frame<B>, app: &App) {
+    draw_idle(f, app);
+
+    let entry = match app.history.entries().last() {
+        Some(entry) => entry,
+        None => return,
+    };
+    let area = centered_rect(50, 50, f.size());
+    let label = Style::default().add_modifier(Modifier::BOLD);
+    let planned = entry.moves.as_ref().map_or(0, |moves| moves.move_count());
+    let optimal = entry.optimal.unwrap_or(planned);
+    let searching = app.searching.is_some();
+
+    let mut text = vec![
+        Spans::from(vec![
+            Span::styled("Your Plan: ", label),
+            Span::raw(
+                entry
+                    .moves
+                    .as_ref()
+                    .map(|moves| moves.to_string())
+                    .unwrap_or_default(),
+            ),
+        ]),
+        Spans::from(vec![
+            Span::styled("Moves: ", label),
+            if searching {
+                Span::raw(planned.to_string())
+            } else {
+                Span::styled(
+                    format!("{} (+{})", planned, planned.saturating_sub(optimal)),
+                    Style::default().fg(if planned > optimal {
+                        Color::LightRed
+                    } else {
+                        Color::LightGreen
+                    }),
+                )
+            },
+            Span::raw(format!("  in {}", entry.time)),
+        ]),
+        Spans::from(""),
+    ];
+    if searching {
+        text.push(Spans::from("Searching for optimal solutions..."));
+        text.push(Spans::from(""));
+        text.push(Spans::from("Press Esc to Skip to the Next Scramble"));
+    } else {
+        text.push(Spans::from(vec![Span::styled(
+            format!("Optimal ({} moves):", optimal),
+            label,
+        )]));
+        text.extend(
+            app.solutions
+                .iter()
+                .map(|solution| Spans::from(solution.to_string())),
+        );
+        text.push(Spans::from(""));
+        text.push(Spans::from("Press Any Key for the Next Scramble"));
+    }
+
+    let block = Block::default()
+        .title(app.config.plan.goal.to_string())
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .style(Style::default().bg(Color::DarkGray))
+        .block(block)
+        .wrap(Wrap { trim: true });
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
@@ -0,0 +1,53 @@
+use crate::{app::App, config::CubeConfig};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+pub fn draw_profiles<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(85), Constraint::Percentage(15)].as_ref())
+        .split(f.size());
+
+    let current = CubeConfig::profile();
+    let rows: Vec<Row> = CubeConfig::profiles()
+        .into_iter()
+        .map(|name| {
+            let in_use = if name == current { "in use" } else { "" };
+            Row::new(vec![name, String::from(in_use)])
+        })
+        .collect();
+    let table = Table::new(rows)
+        .block(
+            Block::default()
+                .title("Profiles")
+                .title_alignment(Alignment::Center)
+                .borders(Borders::ALL),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths([Constraint::Percentage(80), Constraint::Percentage(20)].as_ref())
+        .column_spacing(1);
+    let mut state = TableState::default();
+    state.select(Some(app.profile_selected));
+    f.render_stateful_widget(table, chunks[0], &mut state);
+
+    let help_block = Block::default().borders(Borders::ALL);
+    let mut text = vec![Spans::from(
+        "j/k: Select    <Enter>: Switch to Profile    n: New Profile    q: Back",
+    )];
+    if let Some(message) = &app.message {
+        text.push(Spans::from(vec![Span::styled(
+            message.as_str(),
+            Style::default().fg(Color::LightRed),
+        )]));
+    }
+    let paragraph = Paragraph::new(text)
+        .block(help_block)
+        .alignment(Alignment::Center);
+    f.render_widget(paragraph, chunks[1]);
+}
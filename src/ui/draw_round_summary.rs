@@ -0,0 +1,98 @@
+use crate::{
+    app::App,
+    history::{Penalty, SolveTime},
+    round::RoundFormat,
+};
+use std::time::Duration;
+use tui::{
+    backend::Backend,
+    layout::Alignment,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use super::{centered_rect, draw_idle};
+
+fn format_result(d: Option<Duration>) -> String {
+    match d {
+        Some(d) => SolveTime(d).to_string(),
+        None => String::from("DNF"),
+    }
+}
+
+pub fn draw_round_summary<B: Backend>(f: &mut Frame<B>, app: &App) {
+    draw_idle(f, app);
+
+    let area = centered_rect(50, 50, f.size());
+    let round = &app.config.round;
+    let solves = app.round_solves();
+    let results: Vec<Option<Duration>> = solves
+        .iter()
+        .map(|entry| match entry.penalty {
+            Penalty::No | Penalty::Time => Some(entry.time.0),
+            _ => None,
+        })
+        .collect();
+    let (single, average) = round.result(&solves);
+
+    // the best and the worst solve of an average of 5 don't count
+    let key = |i: &usize| results[*i].unwrap_or(Duration::MAX);
+    let dropped: Vec<usize> =
+        if round.format == RoundFormat::Ao5 && results.len() == round.format.solves() {
+            let indices = 0..results.len();
+            [indices.clone().min_by_key(key), indices.max_by_key(key)]
+                .into_iter()
+                .flatten()
+                .collect()
+        } else {
+            vec![]
+        };
+
+    let label = Style::default().add_modifier(Modifier::BOLD);
+    let mut text: Vec<Spans> = results
+        .iter()
+        .enumerate()
+        .map(|(i, &result)| {
+            let result = format_result(result);
+            Spans::from(format!(
+                "{}. {}",
+                i + 1,
+                if dropped.contains(&i) {
+                    format!("({})", result)
+                } else {
+                    result
+                }
+            ))
+        })
+        .collect();
+    text.push(Spans::from(""));
+    if round.made_cutoff(&solves) == Some(false) {
+        text.push(Spans::from("Cutoff missed"));
+    }
+    text.push(Spans::from(vec![
+        Span::styled(format!("{}: ", round.format), label),
+        Span::styled(
+            average.map_or_else(|| String::from("-"), format_result),
+            Style::default().fg(Color::LightGreen),
+        ),
+    ]));
+    text.push(Spans::from(vec![
+        Span::styled("Single: ", label),
+        Span::raw(format_result(single)),
+    ]));
+    text.push(Spans::from(""));
+    text.push(Spans::from("Press Any Key to Start the Next Round"));
+
+    let summary_block = Block::default()
+        .title("Round Summary")
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .style(Style::default().bg(Color::DarkGray))
+        .block(summary_block);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
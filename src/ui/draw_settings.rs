@@ -0,0 +1,68 @@
+use crate::{app::App, settings::SETTINGS};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+pub fn draw_settings<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let settings = match &app.settings {
+        Some(settings) => settings,
+        None => return,
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(85), Constraint::Percentage(15)].as_ref())
+        .split(f.size());
+
+    let rows: Vec<Row> = SETTINGS
+        .iter()
+        .map(|setting| {
+            Row::new(vec![
+                Cell::from(setting.name()),
+                Cell::from(settings.get(setting).unwrap_or_else(|| String::from("-")))
+                    .style(Style::default().fg(Color::LightGreen)),
+                Cell::from(setting.help),
+            ])
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Setting", "Value", "Description"]))
+        .block(
+            Block::default()
+                .title("Settings")
+                .title_alignment(Alignment::Center)
+                .borders(Borders::ALL),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(
+            [
+                Constraint::Percentage(25),
+                Constraint::Percentage(25),
+                Constraint::Percentage(50),
+            ]
+            .as_ref(),
+        )
+        .column_spacing(1);
+    let mut state = TableState::default();
+    state.select(Some(app.setting_selected));
+    f.render_stateful_widget(table, chunks[0], &mut state);
+
+    let help_block = Block::default().borders(Borders::ALL);
+    let mut text = vec![Spans::from(
+        "j/k: Select    h/l: Previous / Next Value    <Enter>: Edit    x: Leave Out    q: Back",
+    )];
+    if let Some(message) = &app.message {
+        text.push(Spans::from(vec![Span::styled(
+            message.as_str(),
+            Style::default().fg(Color::LightRed),
+        )]));
+    }
+    let paragraph = Paragraph::new(text)
+        .block(help_block)
+        .alignment(Alignment::Center);
+    f.render_widget(paragraph, chunks[1]);
+}
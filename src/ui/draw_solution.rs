@@ -0,0 +1,70 @@
+use crate::app::App;
+use tui::{
+    backend::Backend,
+    layout::Alignment,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use super::{centered_rect, draw_detail};
+
+pub fn draw_solution<B: Backend>(f: &mut Frame<B>, app: &App) {
+    draw_detail(f, app);
+
+    let area = centered_rect(70, 70, f.size());
+    let block = Block::default()
+        .title("Solver")
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    let solution = match &app.solution {
+        Some(solution) => solution,
+        None => {
+            let text = vec![
+                Spans::from("Solving..."),
+                Spans::from(""),
+                Spans::from("Press Any Key to Go Back"),
+            ];
+            let paragraph = Paragraph::new(text)
+                .alignment(Alignment::Center)
+                .style(Style::default().bg(Color::DarkGray))
+                .block(block);
+            f.render_widget(Clear, area);
+            f.render_widget(paragraph, area);
+            return;
+        }
+    };
+    let label = Style::default().add_modifier(Modifier::BOLD);
+
+    let mut text = vec![
+        Spans::from(vec![Span::styled(
+            format!("Solution ({} moves):", solution.moves.moves().len()),
+            label,
+        )]),
+        Spans::from(Span::styled(
+            solution.moves.to_string(),
+            Style::default().fg(Color::LightGreen),
+        )),
+    ];
+    for (method, steps) in &solution.steps {
+        text.push(Spans::from(""));
+        text.push(Spans::from(Span::styled(method.to_string(), label)));
+        text.extend(steps.iter().map(|step| {
+            Spans::from(vec![
+                Span::styled(format!("{}: ", step.name), label),
+                Span::raw(format!("{} ({})", step.moves, step.moves.moves().len())),
+            ])
+        }));
+    }
+    text.push(Spans::from(""));
+    text.push(Spans::from("Press Any Key to Go Back"));
+
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .style(Style::default().bg(Color::DarkGray))
+        .block(block)
+        .wrap(Wrap { trim: true });
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
@@ -1,4 +1,4 @@
-use crate::app::App;
+use crate::{app::App, config::Mode};
 use cfonts::{render, Fonts, Options};
 use tui::{
     backend::Backend,
@@ -19,21 +19,40 @@ pub fn draw_timer<B: Backend>(f: &mut Frame<B>, app: &App) {
         .title_alignment(Alignment::Center)
         .borders(Borders::ALL);
     let timer_font = render(Options {
-        text: app.timer.to_string(),
+        text: if app.config.session.hide_timer {
+            String::from("--.---")
+        } else {
+            app.timer.to_string()
+        },
         font: Fonts::FontHuge,
         ..Options::default()
     });
-    let timer_text = Text::styled(
-        format!("{}", timer_font.text),
-        Style::default().fg(Color::LightGreen),
-    );
+    let timer_text = Text::styled(timer_font.text, Style::default().fg(Color::LightGreen));
     let paragraph = Paragraph::new(timer_text)
         .block(timer_block)
         .alignment(Alignment::Center);
     f.render_widget(paragraph, chunks[0]);
 
     let help_block = Block::default().borders(Borders::ALL);
-    let text = vec![Spans::from("Press Any Key to Stop Timer")];
+    let session = &app.config.session;
+    let phase = app.timer.splits.len();
+    let text = if phase + 1 < session.phases() && session.mode == Mode::Relay {
+        vec![Spans::from(format!(
+            "{} ({}/{}): Press Any Key to Split, <Space> to Stop Timer",
+            session.phase_name(phase),
+            phase + 1,
+            session.phases()
+        ))]
+    } else if phase + 1 < session.phases() {
+        vec![Spans::from(format!(
+            "{} ({}/{}): Press Any Key to Split",
+            session.phase_name(phase),
+            phase + 1,
+            session.phases()
+        ))]
+    } else {
+        vec![Spans::from("Press Any Key to Stop Timer")]
+    };
     let paragraph = Paragraph::new(text)
         .block(help_block)
         .alignment(Alignment::Center);
@@ -0,0 +1,44 @@
+use crate::app::App;
+use tui::{
+    backend::Backend,
+    layout::Alignment,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use super::centered_rect;
+
+pub fn draw_typing<B: Backend>(f: &mut Frame<B>, app: &App, title: &str, example: &str) {
+    let area = centered_rect(50, 30, f.size());
+
+    let typing_block = Block::default()
+        .title(title)
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL);
+    let mut text = vec![
+        Spans::from(vec![Span::styled(
+            format!("{}_", app.input),
+            Style::default()
+                .fg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+        )]),
+        Spans::from(""),
+        Spans::from(example),
+        Spans::from("<Enter> to Record, <Esc> to Stop Typing"),
+    ];
+    if let Some(err) = &app.input_error {
+        text.push(Spans::from(""));
+        text.push(Spans::from(vec![Span::styled(
+            err.as_str(),
+            Style::default().fg(Color::LightRed),
+        )]));
+    }
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .style(Style::default().bg(Color::DarkGray))
+        .block(typing_block);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
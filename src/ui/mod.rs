@@ -1,14 +1,36 @@
 mod draw;
 mod draw_confirm_dialog;
+mod draw_detail;
+mod draw_drill;
+mod draw_fmc;
 mod draw_help;
+mod draw_history_report;
 mod draw_idle;
 mod draw_inspecting;
+mod draw_library;
+mod draw_plan_result;
+mod draw_profiles;
+mod draw_round_summary;
+mod draw_settings;
+mod draw_solution;
 mod draw_timer;
+mod draw_typing;
 
 pub use draw::draw;
 
-pub(self) use draw_confirm_dialog::draw_confirm_dialog;
-pub(self) use draw_help::draw_help;
-pub(self) use draw_idle::draw_idle;
-pub(self) use draw_inspecting::draw_inspecting;
-pub(self) use draw_timer::draw_timer;
+use draw_confirm_dialog::{centered_rect, draw_confirm_dialog};
+use draw_detail::draw_detail;
+use draw_drill::draw_drill;
+use draw_fmc::draw_fmc;
+use draw_help::draw_help;
+use draw_history_report::draw_history_report;
+use draw_idle::draw_idle;
+use draw_inspecting::draw_inspecting;
+use draw_library::draw_library;
+use draw_plan_result::draw_plan_result;
+use draw_profiles::draw_profiles;
+use draw_round_summary::draw_round_summary;
+use draw_settings::draw_settings;
+use draw_solution::draw_solution;
+use draw_timer::draw_timer;
+use draw_typing::draw_typing;